@@ -0,0 +1,36 @@
+use structopt::StructOpt;
+use tensorflow_serving::TensorflowServing;
+
+#[derive(StructOpt, Debug)]
+struct Opts {
+    #[structopt(short = "m", long = "model")]
+    model: String,
+    #[structopt(long = "hostname", default_value = "127.0.0.1")]
+    hostname: String,
+    #[structopt(long = "port", default_value = "9000")]
+    port: u16,
+}
+
+fn main() {
+    let opts = Opts::from_args();
+
+    let mut serving = TensorflowServing::new()
+        .hostname(opts.hostname)
+        .port(opts.port)
+        .build_blocking()
+        .unwrap();
+
+    println!("Tensorflow serving client created");
+
+    println!("Getting model status");
+    let status = serving
+        .model_status(&opts.model)
+        .expect("fetching model status");
+    println!("Got result: {:#?}", status);
+
+    println!("Fetching metadata");
+    let metadata = serving
+        .model_metadata(&opts.model)
+        .expect("fetching model metadata");
+    println!("Got result: {:#?}", metadata);
+}
@@ -14,6 +14,9 @@ struct Opts {
     hostname: String,
     #[structopt(long = "port", default_value = "9000")]
     port: u16,
+    /// Only return these outputs (may be given multiple times)
+    #[structopt(long = "output")]
+    outputs: Vec<String>,
 }
 
 #[tokio::main]
@@ -36,11 +39,12 @@ async fn main() {
     let model_definition = tensorflow_serving::ModelDescription {
         name: opts.model,
         version: opts.model_version,
+        signature_name: None,
     };
 
     println!("Sending request");
     let result = serving
-        .predict_with_preprocessing(img, model_definition, |value| value / 255.)
+        .predict_with_output_filter(img, model_definition, |value| value / 255., opts.outputs)
         .await
         .expect("error predicting");
     println!("Got result: {:#?}", result);
@@ -0,0 +1,57 @@
+use futures::future::join_all;
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+use tensorflow_serving::TensorflowServing;
+use tokio::sync::Mutex;
+
+#[derive(StructOpt, Debug)]
+struct Opts {
+    #[structopt(parse(from_os_str))]
+    image: PathBuf,
+    #[structopt(short = "m", long = "model")]
+    model: String,
+    #[structopt(long = "hostname", default_value = "127.0.0.1")]
+    hostname: String,
+    #[structopt(long = "port", default_value = "9000")]
+    port: u16,
+    /// Number of tasks sharing the client
+    #[structopt(long = "tasks", default_value = "4")]
+    tasks: usize,
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let opts = Opts::from_args();
+
+    let img = image::open(&opts.image).expect("reading image");
+
+    let serving = TensorflowServing::new()
+        .hostname(opts.hostname)
+        .port(opts.port)
+        .build()
+        .await
+        .unwrap();
+
+    // The client is held across `.await`, so it needs an async mutex rather than the std one
+    let serving = Arc::new(Mutex::new(serving));
+
+    let model = opts.model;
+    let futs = (0..opts.tasks).map(|task| {
+        let serving = serving.clone();
+        let img = img.clone();
+        let model = model.clone();
+        tokio::spawn(async move {
+            let mut serving = serving.lock().await;
+            let result = serving.predict(img, model).await.expect("error predicting");
+            println!("Task {}: class {}", task, result.max_idx);
+        })
+    });
+
+    for res in join_all(futs).await {
+        if let Err(e) = res {
+            eprintln!("failure: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{Result, SignatureDefMap};
+use prost::Message;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Type URL of the signature definitions in model metadata
+pub(crate) const SIGNATURE_DEF_MAP_URL: &str =
+    "type.googleapis.com/tensorflow.serving.SignatureDefMap";
+
+/// Decodes the value of a `prost_types::Any` into a message
+pub type AnyDecoder = Arc<dyn Fn(&[u8]) -> Result<Box<dyn Any + Send + Sync>> + Send + Sync>;
+
+/// Decoders for `prost_types::Any` values, keyed by type URL
+///
+/// Model metadata, and some advanced signatures, wrap their messages in `Any`. Register the
+/// message types you expect, then decode values without matching on type URLs yourself. The
+/// default registry decodes the `SignatureDefMap` returned as the `signature_def` model
+/// metadata, and [new](#method.new) creates an empty one.
+///
+/// ```rust
+/// # use tensorflow_serving::{AnyRegistry, TensorShapeProto};
+/// # use prost::Message;
+/// let url = "type.googleapis.com/tensorflow.TensorShapeProto";
+/// let mut registry = AnyRegistry::default();
+/// registry.register::<TensorShapeProto, _>(url);
+///
+/// let mut value = Vec::new();
+/// TensorShapeProto::default().encode(&mut value).unwrap();
+/// let any = prost_types::Any {
+///     type_url: url.to_string(),
+///     value,
+/// };
+/// let shape: TensorShapeProto = registry.decode_as(&any).unwrap();
+/// assert!(shape.dim.is_empty());
+/// ```
+#[derive(Clone)]
+pub struct AnyRegistry {
+    decoders: HashMap<String, AnyDecoder>,
+}
+
+impl AnyRegistry {
+    /// Registry without any decoders
+    pub fn new() -> AnyRegistry {
+        AnyRegistry {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Decode values with type URL `type_url` as messages of type `M`
+    ///
+    /// Registering an existing type URL replaces its decoder.
+    pub fn register<M, S>(&mut self, type_url: S) -> &mut Self
+    where
+        M: Message + Default + Send + Sync + 'static,
+        S: Into<String>,
+    {
+        self.register_decoder(
+            type_url,
+            Arc::new(|bytes| Ok(Box::new(M::decode(bytes)?) as Box<dyn Any + Send + Sync>)),
+        )
+    }
+
+    /// Decode values with type URL `type_url` using a custom decoder
+    pub fn register_decoder<S: Into<String>>(
+        &mut self,
+        type_url: S,
+        decoder: AnyDecoder,
+    ) -> &mut Self {
+        self.decoders.insert(type_url.into(), decoder);
+        self
+    }
+
+    /// Whether a decoder is registered for `type_url`
+    pub fn contains(&self, type_url: &str) -> bool {
+        self.decoders.contains_key(type_url)
+    }
+
+    /// Decode a value with the decoder registered for its type URL
+    ///
+    /// Returns an error if no decoder is registered for the type URL, or if the value cannot
+    /// be decoded.
+    pub fn decode(&self, any: &prost_types::Any) -> Result<Box<dyn Any + Send + Sync>> {
+        let decoder = self
+            .decoders
+            .get(&any.type_url)
+            .ok_or_else(|| format!("no decoder registered for {}", any.type_url))?;
+        decoder(&any.value)
+    }
+
+    /// Decode a value, and check it decoded to a message of type `M`
+    pub fn decode_as<M: 'static>(&self, any: &prost_types::Any) -> Result<M> {
+        self.decode(any)?
+            .downcast::<M>()
+            .map(|message| *message)
+            .map_err(|_| format!("{} did not decode to the requested type", any.type_url).into())
+    }
+}
+
+impl Default for AnyRegistry {
+    /// Registry decoding the `SignatureDefMap` from model metadata
+    fn default() -> Self {
+        let mut registry = AnyRegistry::new();
+        registry.register::<SignatureDefMap, _>(SIGNATURE_DEF_MAP_URL);
+        registry
+    }
+}
+
+impl fmt::Debug for AnyRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut type_urls: Vec<_> = self.decoders.keys().collect();
+        type_urls.sort();
+        f.debug_struct("AnyRegistry")
+            .field("type_urls", &type_urls)
+            .finish()
+    }
+}
@@ -0,0 +1,403 @@
+use crate::inflight::Guard;
+use crate::{
+    squeeze_batch, InputError, ModelSpec, PredictResponse, Result, TensorProto,
+    TensorflowServingError,
+};
+use futures::channel::oneshot;
+use prost::Message;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::metadata::MetadataMap;
+
+/// A prediction waiting to be sent as part of a batch
+pub(crate) struct Pending {
+    pub(crate) inputs: HashMap<String, TensorProto>,
+    reply: oneshot::Sender<Result<PredictResponse>>,
+    /// Keeps the call counted as in flight until its outputs have been handed back
+    _in_flight: Guard,
+}
+
+/// Predictions for one model and input shape collected so far
+struct Queue {
+    /// Identifies the batch, as a new queue can be opened under the same key once a full one
+    /// has been flushed
+    id: u64,
+    pending: Vec<Pending>,
+    flush: Option<oneshot::Sender<Vec<Pending>>>,
+}
+
+/// Role of a call after joining a batch
+pub(crate) enum Joined {
+    /// The call opened the batch, and is responsible for sending it
+    ///
+    /// The batch is delivered early through the receiver if it fills up before the delay.
+    /// Otherwise the leader takes it with [`MicroBatcher::take`] and the batch ID.
+    Leader(u64, oneshot::Receiver<Vec<Pending>>),
+    /// The call joined a batch opened by another call
+    Follower,
+}
+
+/// Queues of single predictions waiting to be sent as one batched request
+///
+/// The first call joining an empty queue leads the batch: it waits for up to `max_delay`,
+/// or until `max_size` calls have joined, then sends every queued input as one request and
+/// fans the outputs back. Queues are keyed by model and input shape, so only compatible
+/// inputs are batched together. The queues are shared between clones of the client.
+#[derive(Clone, Default)]
+pub(crate) struct MicroBatcher {
+    config: Option<(Duration, usize)>,
+    queues: Arc<Mutex<HashMap<u64, Queue>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl MicroBatcher {
+    pub(crate) fn new(config: Option<(Duration, usize)>) -> Self {
+        MicroBatcher {
+            config,
+            queues: Default::default(),
+            next_id: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    pub(crate) fn max_delay(&self) -> Duration {
+        self.config.map(|(delay, _)| delay).unwrap_or_default()
+    }
+
+    /// Add inputs to the batch for `key`, returning the receiver for this call's outputs
+    pub(crate) fn join(
+        &self,
+        key: u64,
+        inputs: HashMap<String, TensorProto>,
+        in_flight: Guard,
+    ) -> (Joined, oneshot::Receiver<Result<PredictResponse>>) {
+        let max_size = self.config.map(|(_, size)| size).unwrap_or(1);
+        let (reply, response) = oneshot::channel();
+        let pending = Pending {
+            inputs,
+            reply,
+            _in_flight: in_flight,
+        };
+
+        let mut queues = self.queues.lock().unwrap();
+        let (joined, full) = match queues.entry(key) {
+            Entry::Vacant(entry) => {
+                let (flush, flushed) = oneshot::channel();
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                let queue = entry.insert(Queue {
+                    id,
+                    pending: vec![pending],
+                    flush: Some(flush),
+                });
+                (Joined::Leader(id, flushed), queue.pending.len() >= max_size)
+            }
+            Entry::Occupied(mut entry) => {
+                let queue = entry.get_mut();
+                queue.pending.push(pending);
+                (Joined::Follower, queue.pending.len() >= max_size)
+            }
+        };
+
+        if full {
+            // Removing the queue under the lock means later calls start a new batch
+            if let Some(mut queue) = queues.remove(&key) {
+                if let Some(flush) = queue.flush.take() {
+                    let _ = flush.send(queue.pending);
+                }
+            }
+        }
+        (joined, response)
+    }
+
+    /// Take batch `id` for `key` once the delay has passed, if it did not fill up first
+    ///
+    /// Returns `None` if the batch was flushed, even if another batch has since been opened
+    /// under the same key, so a leader never takes a batch it does not lead.
+    pub(crate) fn take(&self, key: u64, id: u64) -> Option<Vec<Pending>> {
+        let mut queues = self.queues.lock().unwrap();
+        match queues.get(&key) {
+            Some(queue) if queue.id == id => queues.remove(&key).map(|queue| queue.pending),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for MicroBatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MicroBatcher")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Key of the batch a prediction can join
+///
+/// Inputs can only be batched when they target the same model, and have the same names, data
+/// types and shapes apart from the batch dimension. The batch is sent with the metadata of the
+/// call leading it, so calls are only batched together when they carry the same `metadata`,
+/// e.g. the same `x-request-id`.
+pub(crate) fn batch_key(
+    spec: &ModelSpec,
+    inputs: &HashMap<String, TensorProto>,
+    metadata: &MetadataMap,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut buf = Vec::new();
+    spec.encode(&mut buf)
+        .expect("encoding to a Vec cannot fail");
+    hasher.write(&buf);
+
+    let headers = metadata.clone().into_headers();
+    let mut entries: Vec<_> = headers
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_bytes()))
+        .collect();
+    entries.sort();
+    for (key, value) in entries {
+        hasher.write(key.as_bytes());
+        hasher.write(value);
+    }
+
+    let mut names: Vec<_> = inputs.keys().collect();
+    names.sort();
+    for name in names {
+        let tensor = &inputs[name];
+        hasher.write(name.as_bytes());
+        hasher.write_i32(tensor.dtype);
+        for dim in dims(tensor).iter().skip(1) {
+            hasher.write_i64(*dim);
+        }
+    }
+    hasher.finish()
+}
+
+/// Send the outputs of a batched request back to the calls which joined it
+///
+/// `squeeze` removes the batch dimension from each call's outputs, once they have been split.
+pub(crate) fn fan_out(batch: Vec<Pending>, result: Result<PredictResponse>, squeeze: bool) {
+    let rows: Vec<i64> = batch.iter().map(|p| rows(&p.inputs)).collect();
+    match result.and_then(|response| split_response(response, &rows)) {
+        Ok(responses) => {
+            for (pending, mut response) in batch.into_iter().zip(responses) {
+                if squeeze {
+                    response.outputs.values_mut().for_each(squeeze_batch);
+                }
+                let _ = pending.reply.send(Ok(response));
+            }
+        }
+        Err(e) => {
+            for pending in batch {
+                let _ = pending.reply.send(Err(duplicate_error(&e)));
+            }
+        }
+    }
+}
+
+/// Concatenate the inputs of every queued call along the batch dimension
+pub(crate) fn merge_inputs(batch: &[Pending]) -> Result<HashMap<String, TensorProto>> {
+    let mut merged: HashMap<String, TensorProto> = HashMap::new();
+    for pending in batch {
+        for (name, tensor) in &pending.inputs {
+            match merged.get_mut(name) {
+                Some(existing) => append_rows(existing, tensor)?,
+                None => {
+                    merged.insert(name.clone(), tensor.clone());
+                }
+            }
+        }
+    }
+    Ok(merged)
+}
+
+/// Stack tensors with a batch dimension into one tensor, in order
+///
+/// Every tensor must have the same shape apart from the batch dimension.
+pub(crate) fn stack_rows(tensors: Vec<TensorProto>) -> Result<TensorProto> {
+    let mut tensors = tensors.into_iter();
+    let mut stacked = tensors.next().ok_or(InputError::NoInputs)?;
+    let expected = dims(&stacked);
+    for tensor in tensors {
+        let found = dims(&tensor);
+        if found.get(1..) != expected.get(1..) {
+            return Err(InputError::ShapeMismatch { expected, found }.into());
+        }
+        append_rows(&mut stacked, &tensor)?;
+    }
+    Ok(stacked)
+}
+
+fn dims(tensor: &TensorProto) -> Vec<i64> {
+    tensor
+        .tensor_shape
+        .as_ref()
+        .map(|shape| shape.dim.iter().map(|d| d.size).collect())
+        .unwrap_or_default()
+}
+
+/// Size of the batch dimension of a call's inputs
+fn rows(inputs: &HashMap<String, TensorProto>) -> i64 {
+    inputs
+        .values()
+        .next()
+        .and_then(|tensor| dims(tensor).first().copied())
+        .unwrap_or(1)
+}
+
+fn append_rows(existing: &mut TensorProto, tensor: &TensorProto) -> Result<()> {
+    let shape = existing
+        .tensor_shape
+        .as_mut()
+        .filter(|shape| !shape.dim.is_empty())
+        .ok_or("cannot batch inputs without a batch dimension")?;
+    shape.dim[0].size += dims(tensor).first().copied().unwrap_or(1);
+
+    existing.float_val.extend_from_slice(&tensor.float_val);
+    existing.double_val.extend_from_slice(&tensor.double_val);
+    existing.int_val.extend_from_slice(&tensor.int_val);
+    existing.int64_val.extend_from_slice(&tensor.int64_val);
+    existing.bool_val.extend_from_slice(&tensor.bool_val);
+    existing
+        .string_val
+        .extend(tensor.string_val.iter().cloned());
+    existing
+        .scomplex_val
+        .extend_from_slice(&tensor.scomplex_val);
+    existing
+        .tensor_content
+        .extend_from_slice(&tensor.tensor_content);
+    Ok(())
+}
+
+/// Split every batched output into one response per call, by the calls' batch sizes
+///
+/// Outputs without a matching batch dimension, e.g. scalars, are given to every call as-is.
+pub(crate) fn split_response(
+    response: PredictResponse,
+    rows: &[i64],
+) -> Result<Vec<PredictResponse>> {
+    let total: i64 = rows.iter().sum();
+    let mut responses: Vec<_> = rows
+        .iter()
+        .map(|_| PredictResponse {
+            model_spec: response.model_spec.clone(),
+            outputs: HashMap::new(),
+        })
+        .collect();
+
+    for (name, tensor) in response.outputs {
+        let batched = dims(&tensor).first() == Some(&total);
+        let parts = if batched {
+            split_rows(&tensor, rows)?
+        } else {
+            vec![tensor; rows.len()]
+        };
+        for (response, part) in responses.iter_mut().zip(parts) {
+            response.outputs.insert(name.clone(), part);
+        }
+    }
+    Ok(responses)
+}
+
+fn split_rows(tensor: &TensorProto, rows: &[i64]) -> Result<Vec<TensorProto>> {
+    fn chunk<T: Clone>(values: &[T], total: i64, start: i64, count: i64) -> Result<Vec<T>> {
+        if values.len() as i64 % total != 0 {
+            return Err("batched output does not divide evenly between calls".into());
+        }
+        let per_row = values.len() as i64 / total;
+        Ok(values[(start * per_row) as usize..((start + count) * per_row) as usize].to_vec())
+    }
+
+    let total: i64 = rows.iter().sum();
+    let mut start = 0;
+    let mut parts = Vec::with_capacity(rows.len());
+    for &count in rows {
+        let mut part = tensor.clone();
+        if let Some(shape) = &mut part.tensor_shape {
+            shape.dim[0].size = count;
+        }
+        part.float_val = chunk(&tensor.float_val, total, start, count)?;
+        part.double_val = chunk(&tensor.double_val, total, start, count)?;
+        part.int_val = chunk(&tensor.int_val, total, start, count)?;
+        part.int64_val = chunk(&tensor.int64_val, total, start, count)?;
+        part.bool_val = chunk(&tensor.bool_val, total, start, count)?;
+        part.string_val = chunk(&tensor.string_val, total, start, count)?;
+        part.scomplex_val = chunk(&tensor.scomplex_val, total, start, count)?;
+        part.tensor_content = chunk(&tensor.tensor_content, total, start, count)?;
+        parts.push(part);
+        start += count;
+    }
+    Ok(parts)
+}
+
+/// Copy of a batch failure for each call which joined the batch
+///
+/// Server errors keep their status code, other errors are passed on as their message.
+fn duplicate_error(error: &TensorflowServingError) -> TensorflowServingError {
+    match error {
+        TensorflowServingError::Status(status) => {
+            tonic::Status::new(status.code(), status.message()).into()
+        }
+        other => TensorflowServingError::Other(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inflight::InFlight;
+
+    #[test]
+    fn leader_does_not_take_a_newer_batch_after_its_own_filled() {
+        let batcher = MicroBatcher::new(Some((Duration::from_secs(1), 2)));
+        let key = 1;
+        let in_flight = InFlight::default();
+        let join = |inputs| batcher.join(key, inputs, in_flight.start().unwrap());
+
+        // The first batch fills up and is flushed to its leader
+        let (first, _) = join(HashMap::new());
+        let (first_id, mut flushed) = match first {
+            Joined::Leader(id, flushed) => (id, flushed),
+            Joined::Follower => panic!("first call should lead"),
+        };
+        assert!(matches!(join(HashMap::new()).0, Joined::Follower));
+
+        // A new call opens a second batch under the same key before the first leader's delay
+        let second_id = match join(HashMap::new()).0 {
+            Joined::Leader(id, _) => id,
+            Joined::Follower => panic!("call after a flush should lead a new batch"),
+        };
+
+        // The first leader's delay fires at the same time: it must not take the second batch
+        assert!(batcher.take(key, first_id).is_none());
+        let batch = flushed.try_recv().unwrap().expect("flushed batch");
+        assert_eq!(batch.len(), 2);
+
+        let batch = batcher.take(key, second_id).expect("second batch");
+        assert_eq!(batch.len(), 1);
+    }
+
+    #[test]
+    fn calls_with_different_metadata_are_not_batched_together() {
+        let spec = ModelSpec::default();
+        let mut inputs = HashMap::new();
+        inputs.insert("input".to_string(), TensorProto::default());
+        let with_request_id = |id: &'static str| {
+            let mut metadata = MetadataMap::new();
+            metadata.insert("x-request-id", id.parse().unwrap());
+            metadata
+        };
+
+        let key = batch_key(&spec, &inputs, &with_request_id("a"));
+        assert_eq!(key, batch_key(&spec, &inputs, &with_request_id("a")));
+        assert_ne!(key, batch_key(&spec, &inputs, &with_request_id("b")));
+        assert_ne!(key, batch_key(&spec, &inputs, &MetadataMap::new()));
+    }
+}
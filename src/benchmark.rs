@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+/// Latency and throughput measured by a [benchmark](struct.TensorflowServing.html#method.benchmark)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkReport {
+    /// Number of successful predictions
+    pub requests: usize,
+    /// Number of failed predictions
+    pub errors: usize,
+    /// Wall clock time the benchmark ran for
+    pub elapsed: Duration,
+    /// Successful predictions per second
+    pub qps: f64,
+    /// Median latency of successful predictions
+    pub p50: Duration,
+    /// 95th percentile latency of successful predictions
+    pub p95: Duration,
+    /// 99th percentile latency of successful predictions
+    pub p99: Duration,
+}
+
+impl BenchmarkReport {
+    /// Summarise the latencies of successful predictions
+    ///
+    /// Percentiles use the nearest rank, and are zero if there were no successful predictions.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::BenchmarkReport;
+    /// # use std::time::Duration;
+    /// let latencies = (1..=100).map(Duration::from_millis).collect();
+    /// let report = BenchmarkReport::from_latencies(latencies, 0, Duration::from_secs(2));
+    /// assert_eq!(report.p50, Duration::from_millis(50));
+    /// assert_eq!(report.p99, Duration::from_millis(99));
+    /// assert_eq!(report.qps, 50.0);
+    /// ```
+    pub fn from_latencies(
+        mut latencies: Vec<Duration>,
+        errors: usize,
+        elapsed: Duration,
+    ) -> BenchmarkReport {
+        latencies.sort();
+        let percentile = |p: f64| {
+            if latencies.is_empty() {
+                return Duration::default();
+            }
+            let rank = (p / 100.0 * latencies.len() as f64).ceil() as usize;
+            latencies[rank.clamp(1, latencies.len()) - 1]
+        };
+
+        BenchmarkReport {
+            requests: latencies.len(),
+            errors,
+            elapsed,
+            qps: latencies.len() as f64 / elapsed.as_secs_f64(),
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        }
+    }
+}
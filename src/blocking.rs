@@ -0,0 +1,149 @@
+//! Blocking Tensorflow Serving client
+//!
+//! The client in this module wraps the async
+//! [`TensorflowServing`](../struct.TensorflowServing.html) client together with a dedicated
+//! tokio runtime, so it can be used from synchronous code without driving a runtime yourself.
+//! It is only available with the `blocking` feature enabled.
+//!
+//! The client is configured with the same builder as the async client, and constructed with
+//! [`build_blocking`](../struct.TensorflowServingBuilder.html#method.build_blocking):
+//!
+//! ```rust,no_run
+//! # use tensorflow_serving::TensorflowServing;
+//! let mut serving = TensorflowServing::new()
+//!     .hostname("localhost")
+//!     .port(9000)
+//!     .build_blocking()
+//!     .unwrap();
+//! let status = serving.model_status("resnet").unwrap();
+//! ```
+//!
+//! The blocking client must not be used from within an async runtime.
+use crate::{
+    ClassificationResult, GetModelMetadataResponse, GetModelStatusResponse, Image, ModelConfig,
+    ModelDescription, Payload, PredictionResult, RegressionResult, ReloadConfigResponse, Result,
+};
+use tokio::runtime::Runtime;
+
+/// Blocking Tensorflow Serving client
+///
+/// See the [module documentation](index.html) for how to construct one.
+#[derive(Debug)]
+pub struct TensorflowServing {
+    inner: crate::TensorflowServing,
+    runtime: Runtime,
+}
+
+impl TensorflowServing {
+    pub(crate) fn connect(builder: &mut crate::TensorflowServingBuilder) -> Result<Self> {
+        let mut runtime = tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()?;
+        let inner = runtime.block_on(builder.build())?;
+        Ok(TensorflowServing { inner, runtime })
+    }
+
+    /// Run a classification (see [classify](../struct.TensorflowServing.html#method.classify))
+    pub fn classify<S, P, T, F, V>(
+        &mut self,
+        model_name: S,
+        payload_map: P,
+    ) -> Result<ClassificationResult>
+    where
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+        P: IntoIterator<Item = (T, V)>,
+        T: Into<String>,
+        V: Into<Payload>,
+    {
+        let inner = &mut self.inner;
+        self.runtime
+            .block_on(inner.classify(model_name, payload_map))
+    }
+
+    /// Run a regression (see [regress](../struct.TensorflowServing.html#method.regress))
+    pub fn regress<S, P, T, F, V>(
+        &mut self,
+        model_name: S,
+        payload_map: P,
+    ) -> Result<RegressionResult>
+    where
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+        P: IntoIterator<Item = (T, V)>,
+        T: Into<String>,
+        V: Into<Payload>,
+    {
+        let inner = &mut self.inner;
+        self.runtime
+            .block_on(inner.regress(model_name, payload_map))
+    }
+
+    /// Run a prediction (see [predict-with-preprocessing][preprocessing])
+    ///
+    /// [preprocessing]: ../struct.TensorflowServing.html#method.predict_with_preprocessing
+    pub fn predict_with_preprocessing<I, F, S, M>(
+        &mut self,
+        img: I,
+        model_description: S,
+        preprocessing_fn: M,
+    ) -> Result<PredictionResult>
+    where
+        I: Image,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+        M: Fn(f32) -> f32,
+    {
+        let inner = &mut self.inner;
+        self.runtime.block_on(inner.predict_with_preprocessing(
+            img,
+            model_description,
+            preprocessing_fn,
+        ))
+    }
+
+    /// Run a prediction (see [predict](../struct.TensorflowServing.html#method.predict))
+    pub fn predict<I, F, S>(&mut self, img: I, model_description: S) -> Result<PredictionResult>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let inner = &mut self.inner;
+        self.runtime.block_on(inner.predict(img, model_description))
+    }
+
+    /// Fetch model status (see [model_status][model-status])
+    ///
+    /// [model-status]: ../struct.TensorflowServing.html#method.model_status
+    pub fn model_status<S, T>(&mut self, model_name: S) -> Result<GetModelStatusResponse>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let inner = &mut self.inner;
+        self.runtime.block_on(inner.model_status(model_name))
+    }
+
+    /// Fetch model metadata (see [model_metadata][model-metadata])
+    ///
+    /// [model-metadata]: ../struct.TensorflowServing.html#method.model_metadata
+    pub fn model_metadata<S, T>(&mut self, model_name: S) -> Result<GetModelMetadataResponse>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let inner = &mut self.inner;
+        self.runtime.block_on(inner.model_metadata(model_name))
+    }
+
+    /// Reload model config (see [reload](../struct.TensorflowServing.html#method.reload))
+    pub fn reload<C>(&mut self, model_config: C) -> Result<ReloadConfigResponse>
+    where
+        C: Into<Vec<ModelConfig>>,
+    {
+        let inner = &mut self.inner;
+        self.runtime.block_on(inner.reload(model_config))
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{GetModelMetadataResponse, ModelSpec, PredictionResult, TensorProto, VersionChoice};
+use prost::Message;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hasher;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    name: String,
+    version: Option<i64>,
+    version_label: Option<String>,
+}
+
+impl From<&ModelSpec> for CacheKey {
+    fn from(spec: &ModelSpec) -> Self {
+        let (version, version_label) = match &spec.version_choice {
+            Some(VersionChoice::Version(v)) => (Some(*v), None),
+            Some(VersionChoice::VersionLabel(l)) => (None, Some(l.clone())),
+            None => (None, None),
+        };
+
+        CacheKey {
+            name: spec.name.clone(),
+            version,
+            version_label,
+        }
+    }
+}
+
+/// Model metadata cache, keyed by model name and version
+///
+/// Entries expire after the configured TTL. Without a TTL nothing is cached. Input names are
+/// small, so they are cached per signature regardless of the TTL, until the model's metadata
+/// is invalidated. The cache is shared between clones of the client.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MetadataCache {
+    ttl: Option<Duration>,
+    entries: Arc<Mutex<HashMap<CacheKey, (Instant, GetModelMetadataResponse)>>>,
+    input_names: Arc<Mutex<InputNames>>,
+}
+
+/// Input names keyed by model and signature name
+type InputNames = HashMap<(CacheKey, String), Vec<String>>;
+
+impl MetadataCache {
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        MetadataCache {
+            ttl,
+            entries: Default::default(),
+            input_names: Default::default(),
+        }
+    }
+
+    pub(crate) fn get(&self, spec: &ModelSpec) -> Option<GetModelMetadataResponse> {
+        let ttl = self.ttl?;
+        let mut entries = self.entries.lock().unwrap();
+        let key = CacheKey::from(spec);
+        match entries.get(&key) {
+            Some((inserted, metadata)) if inserted.elapsed() < ttl => Some(metadata.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, spec: &ModelSpec, metadata: &GetModelMetadataResponse) {
+        if self.ttl.is_some() {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(CacheKey::from(spec), (Instant::now(), metadata.clone()));
+        }
+    }
+
+    pub(crate) fn input_names(&self, spec: &ModelSpec) -> Option<Vec<String>> {
+        let key = (CacheKey::from(spec), spec.signature_name.clone());
+        self.input_names.lock().unwrap().get(&key).cloned()
+    }
+
+    pub(crate) fn insert_input_names(&self, spec: &ModelSpec, names: &[String]) {
+        let key = (CacheKey::from(spec), spec.signature_name.clone());
+        self.input_names.lock().unwrap().insert(key, names.to_vec());
+    }
+
+    pub(crate) fn invalidate(&self, model_name: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|key, _| key.name != model_name);
+        self.input_names
+            .lock()
+            .unwrap()
+            .retain(|(key, _), _| key.name != model_name);
+    }
+}
+
+/// Digest of the encoded model spec and input tensors of a prediction request
+///
+/// Two differently seeded 64-bit hashes along with the encoded length, so the inputs
+/// themselves are not kept alive by the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ResultKey {
+    digest: (u64, u64),
+    len: usize,
+}
+
+/// Prediction result cache, keyed by a digest of the encoded model spec and input tensors
+///
+/// Holds up to `capacity` results, evicting the least recently used. Without a capacity
+/// nothing is cached. Only the digest is kept as the key, so cached entries do not pin copies
+/// of the inputs in memory. The cache is shared between clones of the client.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResultCache {
+    capacity: Option<usize>,
+    entries: Arc<Mutex<LruEntries>>,
+}
+
+#[derive(Debug, Default)]
+struct LruEntries {
+    /// Results along with the tick they were last used at
+    results: HashMap<ResultKey, (PredictionResult, u64)>,
+    /// Keys by the tick they were last used at, from least to most recently used
+    order: BTreeMap<u64, ResultKey>,
+    tick: u64,
+}
+
+impl ResultCache {
+    pub(crate) fn new(capacity: Option<usize>) -> Self {
+        ResultCache {
+            capacity: capacity.filter(|&c| c > 0),
+            entries: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity.is_some()
+    }
+
+    pub(crate) fn get(&self, key: &ResultKey) -> Option<PredictionResult> {
+        let mut entries = self.entries.lock().unwrap();
+        let result = entries.touch(key)?;
+        Some(result.clone())
+    }
+
+    pub(crate) fn insert(&self, key: ResultKey, result: &PredictionResult) {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => return,
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(cached) = entries.touch(&key) {
+            *cached = result.clone();
+            return;
+        }
+        let tick = entries.next_tick();
+        entries.order.insert(tick, key);
+        entries.results.insert(key, (result.clone(), tick));
+        while entries.results.len() > capacity {
+            let oldest = match entries.order.keys().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+            if let Some(key) = entries.order.remove(&oldest) {
+                entries.results.remove(&key);
+            }
+        }
+    }
+}
+
+impl LruEntries {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Mark a key as the most recently used, returning its result if it is cached
+    fn touch(&mut self, key: &ResultKey) -> Option<&mut PredictionResult> {
+        let tick = self.next_tick();
+        let (result, used) = self.results.get_mut(key)?;
+        let key = self.order.remove(used).expect("cached keys are ordered");
+        self.order.insert(tick, key);
+        *used = tick;
+        Some(result)
+    }
+}
+
+/// Digest a prediction request as a cache key, independently of the order of the inputs
+pub(crate) fn result_key(spec: &ModelSpec, inputs: &HashMap<String, TensorProto>) -> ResultKey {
+    let mut buf = Vec::new();
+    spec.encode_length_delimited(&mut buf)
+        .expect("encoding to a Vec cannot fail");
+
+    let mut names: Vec<_> = inputs.keys().collect();
+    names.sort();
+    for name in names {
+        buf.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        buf.extend_from_slice(name.as_bytes());
+        inputs[name]
+            .encode_length_delimited(&mut buf)
+            .expect("encoding to a Vec cannot fail");
+    }
+    let digest = |seed: u64| {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(seed);
+        hasher.write(&buf);
+        hasher.finish()
+    };
+    ResultKey {
+        digest: (digest(0), digest(1)),
+        len: buf.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Payload, PredictResponse};
+
+    fn inputs(value: f32) -> HashMap<String, TensorProto> {
+        let mut inputs = HashMap::new();
+        inputs.insert(
+            "input".to_string(),
+            Payload::Floats(vec![value]).into_tensor(&[1]),
+        );
+        inputs
+    }
+
+    fn result(max_idx: i64) -> PredictionResult {
+        let mut response = PredictResponse::default();
+        response.outputs.insert(
+            "probabilities".into(),
+            Payload::Floats(vec![0.5, 0.5]).into_tensor(&[1, 2]),
+        );
+        response.outputs.insert(
+            "classes".into(),
+            Payload::Ints(vec![max_idx]).into_tensor(&[1]),
+        );
+        PredictionResult::from_raw(response).unwrap()
+    }
+
+    #[test]
+    fn results_are_only_returned_for_identical_requests() {
+        let cache = ResultCache::new(Some(4));
+        let spec = ModelSpec::default();
+        cache.insert(result_key(&spec, &inputs(1.0)), &result(1));
+
+        assert_eq!(
+            cache.get(&result_key(&spec, &inputs(1.0))).unwrap().max_idx,
+            1
+        );
+        assert!(cache.get(&result_key(&spec, &inputs(2.0))).is_none());
+
+        let other = ModelSpec {
+            name: "other".to_string(),
+            ..Default::default()
+        };
+        assert!(cache.get(&result_key(&other, &inputs(1.0))).is_none());
+    }
+
+    #[test]
+    fn least_recently_used_result_is_evicted() {
+        let cache = ResultCache::new(Some(2));
+        let spec = ModelSpec::default();
+        let key = |value| result_key(&spec, &inputs(value));
+        cache.insert(key(1.0), &result(1));
+        cache.insert(key(2.0), &result(2));
+
+        // Using the first result makes the second the least recently used
+        assert!(cache.get(&key(1.0)).is_some());
+        cache.insert(key(3.0), &result(3));
+
+        assert!(cache.get(&key(2.0)).is_none());
+        assert_eq!(cache.get(&key(1.0)).unwrap().max_idx, 1);
+        assert_eq!(cache.get(&key(3.0)).unwrap().max_idx, 3);
+
+        // Replacing a cached result does not evict anything
+        cache.insert(key(3.0), &result(0));
+        assert_eq!(cache.get(&key(3.0)).unwrap().max_idx, 0);
+        assert!(cache.get(&key(1.0)).is_some());
+    }
+}
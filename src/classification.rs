@@ -0,0 +1,141 @@
+use crate::ClassificationResult;
+
+/// Class labels and scores for each example of a classification result
+///
+/// The classes of each example are returned as `(label, score)` pairs, in the order returned
+/// by the server. Labels are protobuf strings, so they are decoded as UTF-8 when the response
+/// is received, and any non-ASCII labels (e.g. localised class names) are kept intact:
+///
+/// ```rust
+/// # use tensorflow_serving::{scored_classes, Class, ClassificationResult, Classifications};
+/// # use prost::Message;
+/// let class = Class {
+///     label: "chat noir 黒猫 🐈".to_string(),
+///     score: 0.9,
+/// };
+/// let result = ClassificationResult {
+///     classifications: vec![Classifications {
+///         classes: vec![class],
+///     }],
+/// };
+///
+/// let mut bytes = Vec::new();
+/// result.encode(&mut bytes).unwrap();
+/// let decoded = ClassificationResult::decode(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(scored_classes(&decoded), vec![vec![("chat noir 黒猫 🐈".to_string(), 0.9)]]);
+/// ```
+pub fn scored_classes(result: &ClassificationResult) -> Vec<Vec<(String, f32)>> {
+    result
+        .classifications
+        .iter()
+        .map(|c| {
+            c.classes
+                .iter()
+                .map(|c| (c.label.clone(), c.score))
+                .collect()
+        })
+        .collect()
+}
+
+/// A single class of a classification result
+///
+/// See [classifications](fn.classifications.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    /// Class label, or `None` if the server returned scores only
+    pub label: Option<String>,
+    /// Score of the class
+    pub score: f32,
+    /// Numeric class ID, if one can be determined
+    pub index: Option<usize>,
+}
+
+/// Typed classes for each example of a classification result
+///
+/// Depending on the model, Tensorflow Serving returns classes with string labels, with class
+/// IDs as labels, or with scores only:
+///
+/// * A purely numeric label, e.g. `"281"`, is kept as the label and also parsed as the
+///   `index`
+/// * Any other label leaves the `index` as `None`
+/// * A class without a label, as returned for models which only output scores, has a
+///   `label` of `None`, and its position in the example's classes as the `index`, since the
+///   server returns the scores in class order
+///
+/// ```rust
+/// # use tensorflow_serving::{classifications, Class, ClassificationResult, Classifications};
+/// let class = |label: &str, score| Class { label: label.to_string(), score };
+/// let result = ClassificationResult {
+///     classifications: vec![Classifications {
+///         classes: vec![class("cat", 0.7), class("281", 0.2), class("", 0.1)],
+///     }],
+/// };
+///
+/// let classes = &classifications(&result)[0];
+/// assert_eq!(classes[0].label.as_deref(), Some("cat"));
+/// assert_eq!(classes[0].index, None);
+/// assert_eq!(classes[1].index, Some(281));
+/// assert_eq!(classes[2].label, None);
+/// assert_eq!(classes[2].index, Some(2));
+/// ```
+pub fn classifications(result: &ClassificationResult) -> Vec<Vec<Classification>> {
+    result
+        .classifications
+        .iter()
+        .map(|c| {
+            c.classes
+                .iter()
+                .enumerate()
+                .map(|(position, class)| {
+                    let numeric =
+                        !class.label.is_empty() && class.label.bytes().all(|b| b.is_ascii_digit());
+                    let (label, index) = if class.label.is_empty() {
+                        (None, Some(position))
+                    } else if numeric {
+                        (Some(class.label.clone()), class.label.parse().ok())
+                    } else {
+                        (Some(class.label.clone()), None)
+                    };
+                    Classification {
+                        label,
+                        score: class.score,
+                        index,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl ClassificationResult {
+    /// Class labels and scores for each example, as `(label, score)` pairs
+    ///
+    /// The method form of [scored_classes](fn.scored_classes.html), so the result of
+    /// [classify](struct.TensorflowServing.html#method.classify) can be used without
+    /// importing the generated proto types. Examples, and the classes within each example,
+    /// keep the order returned by the server.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{Class, ClassificationResult, Classifications};
+    /// let example = |classes: &[(&str, f32)]| Classifications {
+    ///     classes: classes
+    ///         .iter()
+    ///         .map(|&(label, score)| Class { label: label.to_string(), score })
+    ///         .collect(),
+    /// };
+    /// let result = ClassificationResult {
+    ///     classifications: vec![
+    ///         example(&[("cat", 0.7), ("dog", 0.3)]),
+    ///         example(&[("dog", 0.6), ("cat", 0.4)]),
+    ///     ],
+    /// };
+    ///
+    /// let scored = result.to_scored_classes();
+    /// assert_eq!(scored[0], vec![("cat".to_string(), 0.7), ("dog".to_string(), 0.3)]);
+    /// assert_eq!(scored[1], vec![("dog".to_string(), 0.6), ("cat".to_string(), 0.4)]);
+    /// ```
+    pub fn to_scored_classes(&self) -> Vec<Vec<(String, f32)>> {
+        scored_classes(self)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{as_floats, DataType, InputError, Result, TensorProto, TensorflowServingError};
+
+/// A float vector output by an embedding model
+///
+/// See [predict_embedding](struct.TensorflowServing.html#method.predict_embedding).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedding {
+    /// Flattened values in row-major order
+    pub values: Vec<f32>,
+    /// Shape of the output tensor, e.g. `[1, 128]`
+    pub shape: Vec<i64>,
+}
+
+impl Embedding {
+    /// Read an embedding from a `DT_FLOAT` output tensor
+    pub fn from_tensor(tensor: &TensorProto) -> Result<Embedding> {
+        if tensor.dtype != DataType::DtFloat as i32 {
+            return Err(TensorflowServingError::DtypeMismatch {
+                name: "embedding".to_string(),
+                expected: vec![DataType::DtFloat],
+                found: DataType::from_i32(tensor.dtype).unwrap_or(DataType::DtInvalid),
+            });
+        }
+
+        let shape = tensor
+            .tensor_shape
+            .as_ref()
+            .map(|shape| shape.dim.iter().map(|d| d.size).collect())
+            .unwrap_or_default();
+        Ok(Embedding {
+            values: as_floats(tensor)?,
+            shape,
+        })
+    }
+}
+
+/// Scale a vector to unit length
+///
+/// Normalised embeddings can be compared with a dot product. A zero vector has no direction,
+/// so it is returned unchanged rather than filled with NaNs.
+///
+/// ```rust
+/// # use tensorflow_serving::l2_normalize;
+/// assert_eq!(l2_normalize(vec![3.0, 4.0]), vec![0.6, 0.8]);
+/// assert_eq!(l2_normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+/// ```
+pub fn l2_normalize(mut values: Vec<f32>) -> Vec<f32> {
+    let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        values.iter_mut().for_each(|v| *v /= norm);
+    }
+    values
+}
+
+/// Cosine similarity between two vectors, from -1 to 1
+///
+/// Returns an [`InputError::ShapeMismatch`](enum.InputError.html) if the vectors have
+/// different lengths. A zero vector has no direction, so its similarity to any vector is 0.
+///
+/// ```rust
+/// # use tensorflow_serving::cosine_similarity;
+/// assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]).unwrap(), 1.0);
+/// assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).unwrap(), 0.0);
+/// assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]).unwrap(), 0.0);
+/// assert!(cosine_similarity(&[1.0], &[1.0, 2.0]).is_err());
+/// ```
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Result<f32> {
+    if a.len() != b.len() {
+        return Err(InputError::ShapeMismatch {
+            expected: vec![a.len() as i64],
+            found: vec![b.len() as i64],
+        }
+        .into());
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms =
+        a.iter().map(|v| v * v).sum::<f32>().sqrt() * b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(dot / norms)
+}
@@ -0,0 +1,201 @@
+use crate::DataType;
+
+/// Errors returned by the client
+///
+/// Errors are kept in their original form where possible, so callers can distinguish e.g.
+/// transport failures from errors returned by the server:
+///
+/// ```rust
+/// # use tensorflow_serving::TensorflowServingError;
+/// let error = TensorflowServingError::from(tonic::Status::not_found("no model"));
+/// match error {
+///     TensorflowServingError::Status(status) => assert_eq!(status.code(), tonic::Code::NotFound),
+///     other => panic!("unexpected error {}", other),
+/// }
+/// ```
+#[derive(Debug, thiserror::Error)]
+pub enum TensorflowServingError {
+    /// Connecting to the server failed, or the connection was lost
+    #[error("transport error: {0}")]
+    Transport(#[from] tonic::transport::Error),
+    /// The server returned an error status
+    #[error("{}", status_message(.0))]
+    Status(#[from] tonic::Status),
+    /// An image could not be read or decoded
+    #[error("image error: {0}")]
+    Image(#[from] image::ImageError),
+    /// The request inputs are invalid
+    #[error("{0}")]
+    Input(#[from] InputError),
+    /// A file could not be read
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// A protobuf message in a response could not be decoded
+    #[error("decoding response: {0}")]
+    Decode(#[from] prost::DecodeError),
+    /// A string tensor value is not valid UTF-8
+    #[error("{0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    /// A response does not contain the named output
+    #[error("{0} output missing from response")]
+    MissingOutput(String),
+    /// An output tensor has a data type the client cannot read it as
+    #[error(
+        "{name} output has data type {}, expected {}",
+        dtype_name(*.found),
+        .expected.iter().map(|d| dtype_name(*d)).collect::<Vec<_>>().join(" or ")
+    )]
+    DtypeMismatch {
+        /// Name of the output
+        name: String,
+        /// Data types the output can be read from
+        expected: Vec<DataType>,
+        /// Data type of the output
+        found: DataType,
+    },
+    /// The server answered with an older model version than the client requires
+    #[error("model version {version} is older than the minimum version {min_version}")]
+    VersionTooOld {
+        /// Version which answered the request
+        version: i64,
+        /// Minimum version configured on the client
+        min_version: i64,
+    },
+    /// A replica endpoint was requested which the client does not have
+    #[error("endpoint index {index} out of range, the client has {replicas} endpoints")]
+    NoReplicas {
+        /// Index of the requested endpoint
+        index: usize,
+        /// Number of endpoints the client was built with
+        replicas: usize,
+    },
+    /// A multi inference result does not match the task at the same position
+    #[error("inference result {task} does not match the {method_name} task")]
+    TaskMismatch {
+        /// Position of the task and result
+        task: usize,
+        /// Method name of the task, e.g. `tensorflow/serving/classify`
+        method_name: String,
+    },
+    /// The client configuration is invalid, e.g. a required option was not provided
+    #[error("invalid client configuration: {0}")]
+    Builder(String),
+    /// Any other error
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Alias of [`TensorflowServingError`]
+pub type TensorServingError = TensorflowServingError;
+
+fn status_message(status: &tonic::Status) -> String {
+    if status.code() == tonic::Code::NotFound {
+        format!("model not found: {}", status.message())
+    } else {
+        format!("server returned {:?}: {}", status.code(), status.message())
+    }
+}
+
+/// Protobuf name of a data type, e.g. `DT_INT64`
+fn dtype_name(dtype: DataType) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{:?}", dtype).chars().enumerate() {
+        if i > 0 && c.is_ascii_uppercase() {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+impl TensorflowServingError {
+    /// gRPC status code of an error returned by the server
+    ///
+    /// Returns `None` for errors detected by the client, including transport failures. Useful
+    /// for deciding how to handle a failed call, e.g. retrying on `Code::Unavailable` while
+    /// reporting `Code::NotFound`, which the server returns for an unknown model name:
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::TensorflowServingError;
+    /// # use tonic::{Code, Status};
+    /// let error = TensorflowServingError::from(Status::not_found("Servable not found"));
+    /// assert_eq!(error.status_code(), Some(Code::NotFound));
+    /// assert_eq!(error.to_string(), "model not found: Servable not found");
+    ///
+    /// let error = TensorflowServingError::from("some client error");
+    /// assert_eq!(error.status_code(), None);
+    /// ```
+    pub fn status_code(&self) -> Option<tonic::Code> {
+        match self {
+            TensorflowServingError::Status(status) => Some(status.code()),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for TensorflowServingError {
+    fn from(msg: String) -> Self {
+        TensorflowServingError::Other(msg)
+    }
+}
+
+impl From<&str> for TensorflowServingError {
+    fn from(msg: &str) -> Self {
+        TensorflowServingError::Other(msg.to_string())
+    }
+}
+
+/// Errors caused by invalid inputs to a request
+///
+/// These are detected by the client before a request is sent to the server.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum InputError {
+    /// The request has no input tensors
+    #[error("no input tensors provided")]
+    NoInputs,
+    /// The request inputs do not match the inputs of the model signature
+    #[error("inputs do not match signature: missing {missing:?}, unexpected {unexpected:?}")]
+    InputMismatch {
+        /// Inputs expected by the signature which were not provided
+        missing: Vec<String>,
+        /// Inputs provided which the signature does not expect
+        unexpected: Vec<String>,
+    },
+    /// A tensor does not have a consistent shape
+    #[error("shape mismatch: expected {expected:?}, found {found:?}")]
+    ShapeMismatch {
+        /// Shape expected from the other values
+        expected: Vec<i64>,
+        /// Shape found
+        found: Vec<i64>,
+    },
+    /// No model has been registered under the alias
+    #[error("no model registered as {0}")]
+    UnknownAlias(String),
+    /// A feature or input name was given more than once
+    #[error("duplicate feature {0}")]
+    DuplicateFeature(String),
+    /// An input tensor was given an empty name
+    #[error("input tensor names must not be empty")]
+    EmptyInputName,
+    /// The number of values does not match the shape they were given
+    #[error("{values} values do not fit shape {shape:?}")]
+    ValueCount {
+        /// Shape given for the values
+        shape: Vec<i64>,
+        /// Number of values given
+        values: usize,
+    },
+    /// Preprocessing produced a NaN or infinite value
+    #[error("preprocessing produced non-finite value {value} at index {index}")]
+    NonFiniteInput {
+        /// Position of the value in the preprocessed samples
+        index: usize,
+        /// The non-finite value
+        value: f32,
+    },
+    /// Normalized values were to be sent in a tensor of this integer data type, which would
+    /// round and clamp them away
+    #[error("normalized values cannot be sent as integer data type {0:?}")]
+    NormalizedIntegerInput(DataType),
+}
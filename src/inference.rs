@@ -0,0 +1,110 @@
+use crate::{inference_result, InferenceResult, ModelDescription};
+
+/// Method name of classification signatures
+const CLASSIFY_METHOD: &str = "tensorflow/serving/classify";
+/// Method name of regression signatures
+const REGRESS_METHOD: &str = "tensorflow/serving/regress";
+
+/// Kind of a [`Task`](enum.Task.html), for building tasks from `(TaskType, model)` pairs
+///
+/// ```rust
+/// # use tensorflow_serving::{Task, TaskType};
+/// let task: Task = (TaskType::Regress, "census").into();
+/// assert_eq!(task.method_name(), "tensorflow/serving/regress");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskType {
+    /// Run a classification signature
+    Classify,
+    /// Run a regression signature
+    Regress,
+}
+
+/// A task to run as part of a
+/// [multi_inference](struct.TensorflowServing.html#method.multi_inference) call
+///
+/// Each task names the signature method to run, so the request carries the right
+/// `method_name` without spelling it out:
+///
+/// ```rust
+/// # use tensorflow_serving::Task;
+/// let tasks = vec![Task::classify("census"), Task::regress("census")];
+/// assert_eq!(tasks[1].method_name(), "tensorflow/serving/regress");
+/// ```
+#[derive(Debug, Clone)]
+pub enum Task {
+    /// Run a classification signature
+    Classify(ModelDescription<String>),
+    /// Run a regression signature
+    Regress(ModelDescription<String>),
+}
+
+impl Task {
+    /// Classification task for a model
+    pub fn classify<S, F>(model_description: S) -> Task
+    where
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        Task::Classify(model_description.into().into_owned())
+    }
+
+    /// Regression task for a model
+    pub fn regress<S, F>(model_description: S) -> Task
+    where
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        Task::Regress(model_description.into().into_owned())
+    }
+
+    /// Signature method name sent for the task
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            Task::Classify(_) => CLASSIFY_METHOD,
+            Task::Regress(_) => REGRESS_METHOD,
+        }
+    }
+
+    /// Model the task runs against
+    pub fn model_description(&self) -> &ModelDescription<String> {
+        match self {
+            Task::Classify(desc) | Task::Regress(desc) => desc,
+        }
+    }
+
+    /// Kind of the task
+    pub fn task_type(&self) -> TaskType {
+        match self {
+            Task::Classify(_) => TaskType::Classify,
+            Task::Regress(_) => TaskType::Regress,
+        }
+    }
+
+    /// Whether a result holds the kind of output this task produces
+    pub(crate) fn matches(&self, result: &InferenceResult) -> bool {
+        use inference_result::Result::{ClassificationResult, RegressionResult};
+
+        matches!(
+            (self, &result.result),
+            (Task::Classify(_), Some(ClassificationResult(_)))
+                | (Task::Regress(_), Some(RegressionResult(_)))
+        )
+    }
+}
+
+impl<S: Into<String>> From<(TaskType, ModelDescription<S>)> for Task {
+    fn from((task_type, model_description): (TaskType, ModelDescription<S>)) -> Self {
+        match task_type {
+            TaskType::Classify => Task::classify(model_description),
+            TaskType::Regress => Task::regress(model_description),
+        }
+    }
+}
+
+/// Tasks for a model given by name
+impl<S: Into<String>> From<(TaskType, S)> for Task {
+    fn from((task_type, model_name): (TaskType, S)) -> Self {
+        (task_type, ModelDescription::from(model_name)).into()
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Count of in-flight calls, shared between clones of a client
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InFlight {
+    state: Arc<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    count: AtomicUsize,
+    closed: AtomicBool,
+    /// Signalled whenever the last in-flight call finishes
+    idle: watch::Sender<()>,
+    idle_rx: watch::Receiver<()>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        let (idle, idle_rx) = watch::channel(());
+        State {
+            count: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            idle,
+            idle_rx,
+        }
+    }
+}
+
+impl InFlight {
+    /// Register the start of a call, returning `None` once the client has been shut down
+    ///
+    /// The call is counted as in flight until the returned guard is dropped.
+    pub(crate) fn start(&self) -> Option<Guard> {
+        // Increment before checking, so `shutdown` cannot miss a call which got through
+        self.state.count.fetch_add(1, Ordering::SeqCst);
+        let guard = Guard {
+            state: self.state.clone(),
+        };
+        if self.state.closed.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Stop new calls from starting and wait for the in-flight ones to finish
+    pub(crate) async fn shutdown(&self) {
+        self.state.closed.store(true, Ordering::SeqCst);
+        // A fresh receiver returns at once on its first `recv`, and a guard dropped after the
+        // count is checked bumps the version, so no wake-up can be missed
+        let mut idle = self.state.idle_rx.clone();
+        while self.state.count.load(Ordering::SeqCst) > 0 {
+            if idle.recv().await.is_none() {
+                break;
+            }
+        }
+    }
+}
+
+/// Marks a call as in flight until dropped
+pub(crate) struct Guard {
+    state: Arc<State>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.state.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _ = self.state.idle.broadcast(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn shutdown_waits_for_the_last_call() {
+        let in_flight = InFlight::default();
+        let first = in_flight.start().unwrap();
+        let second = in_flight.start().unwrap();
+
+        let mut shutdown = tokio::spawn({
+            let in_flight = in_flight.clone();
+            async move { in_flight.shutdown().await }
+        });
+        tokio::time::delay_for(Duration::from_millis(20)).await;
+        assert!(in_flight.start().is_none());
+
+        drop(first);
+        let waiting = tokio::time::timeout(Duration::from_millis(20), &mut shutdown).await;
+        assert!(waiting.is_err(), "shutdown finished with a call in flight");
+
+        drop(second);
+        tokio::time::timeout(Duration::from_secs(1), shutdown)
+            .await
+            .expect("shutdown did not finish after the last call")
+            .unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{image_to_tensor, DataType, InputError, Layout, Payload, Result, TensorProto};
+use image::DynamicImage;
+use std::collections::HashMap;
+
+/// A named input of a multi-input prediction, before it is converted to a tensor
+///
+/// Used with [build_inputs](fn.build_inputs.html) to build the inputs for
+/// [predict_multi](struct.TensorflowServing.html#method.predict_multi) from a mix of images
+/// and plain values.
+#[derive(Debug, Clone)]
+pub enum InputValue {
+    /// An image, sent as a `[1, height, width, 3]` float tensor of unscaled pixel values
+    Image(DynamicImage),
+    /// Flattened float values in row-major order, with the shape of the tensor
+    Floats(Vec<f32>, Vec<i64>),
+    /// A tensor which has already been built
+    Tensor(TensorProto),
+}
+
+impl InputValue {
+    /// Image input
+    pub fn image(img: DynamicImage) -> InputValue {
+        InputValue::Image(img)
+    }
+
+    /// Float input with the given shape
+    pub fn floats<V: Into<Vec<f32>>>(values: V, shape: &[i64]) -> InputValue {
+        InputValue::Floats(values.into(), shape.to_vec())
+    }
+
+    fn into_tensor(self) -> Result<TensorProto> {
+        match self {
+            InputValue::Image(img) => image_to_tensor(img, |p| p, Layout::Nhwc, DataType::DtFloat),
+            InputValue::Floats(values, shape) => {
+                if shape.iter().product::<i64>() != values.len() as i64 {
+                    return Err(InputError::ValueCount {
+                        shape,
+                        values: values.len(),
+                    }
+                    .into());
+                }
+                Ok(Payload::Floats(values).into_tensor(&shape))
+            }
+            InputValue::Tensor(tensor) => Ok(tensor),
+        }
+    }
+}
+
+impl From<DynamicImage> for InputValue {
+    fn from(img: DynamicImage) -> Self {
+        InputValue::Image(img)
+    }
+}
+
+impl From<TensorProto> for InputValue {
+    fn from(tensor: TensorProto) -> Self {
+        InputValue::Tensor(tensor)
+    }
+}
+
+/// Build the inputs of a multi-input prediction from `(name, input)` pairs
+///
+/// Returns an [`InputError`](enum.InputError.html) if a name is empty or given more than
+/// once, or if the number of float values does not match their shape.
+///
+/// ```rust
+/// # use tensorflow_serving::{build_inputs, InputValue};
+/// let img = image::DynamicImage::new_rgb8(4, 2);
+/// let inputs = build_inputs(vec![
+///     ("image", InputValue::image(img)),
+///     ("metadata", InputValue::floats(vec![0.5, 1.0], &[1, 2])),
+/// ])
+/// .unwrap();
+/// assert_eq!(inputs["metadata"].float_val, vec![0.5, 1.0]);
+/// assert_eq!(inputs["image"].float_val.len(), 4 * 2 * 3);
+/// ```
+pub fn build_inputs<I, N>(pairs: I) -> Result<HashMap<String, TensorProto>>
+where
+    I: IntoIterator<Item = (N, InputValue)>,
+    N: Into<String>,
+{
+    let mut inputs = HashMap::new();
+    for (name, input) in pairs {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(InputError::EmptyInputName.into());
+        }
+        if inputs.contains_key(&name) {
+            return Err(InputError::DuplicateFeature(name).into());
+        }
+        inputs.insert(name, input.into_tensor()?);
+    }
+    Ok(inputs)
+}
+
+/// Check every input of a request has a name
+pub(crate) fn validate_names(inputs: &HashMap<String, TensorProto>) -> Result<()> {
+    if inputs.keys().any(|name| name.is_empty()) {
+        return Err(InputError::EmptyInputName.into());
+    }
+    Ok(())
+}
@@ -1,14 +1,50 @@
 #![allow(clippy::large_enum_variant)]
 extern crate prost_types;
 
-use image::{DynamicImage, GenericImageView};
+use futures::future::{self, Either};
+use futures::stream::{self, Stream};
+use image::imageops::FilterType;
+use image::{AnimationDecoder, DynamicImage, GenericImageView};
+use log::debug;
+use prost::Message;
 use std::collections::HashMap;
-use std::error::Error;
-use std::iter::FromIterator;
+use std::fmt;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tonic::metadata::{Ascii, MetadataMap, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+
+mod any;
+mod batching;
+mod benchmark;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cache;
+mod classification;
+mod embedding;
+mod error;
+mod inference;
+mod inflight;
+mod inputs;
+mod observer;
+mod prediction;
+mod preprocessing;
+mod retry;
+mod service;
+mod signature;
+mod stats;
+mod status;
+mod tensor;
+#[cfg(test)]
+mod test_server;
+mod tiling;
+#[cfg(feature = "tls")]
+mod tls;
 
 /// Our custom result type
-pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+pub type Result<T> = std::result::Result<T, TensorflowServingError>;
 
 /// Trait representing either an image or some image data
 ///
@@ -42,6 +78,59 @@ impl Image for &str {
     }
 }
 
+/// Trait representing the bytes of an encoded image (e.g. JPEG or PNG)
+///
+/// Paths are read but not decoded, so models which take encoded images receive the file
+/// contents verbatim.
+pub trait EncodedImage {
+    /// Extract the encoded image bytes from the enclosed object
+    ///
+    fn to_bytes(&self) -> Result<Vec<u8>>;
+
+    /// Build a `DT_STRING` tensor holding the encoded image bytes
+    ///
+    fn to_tensor(&self) -> Result<TensorProto> {
+        Ok(Payload::Bytes(vec![self.to_bytes()?]).into_tensor(&[1]))
+    }
+}
+
+impl EncodedImage for Path {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        std::fs::read(self).map_err(From::from)
+    }
+}
+
+impl EncodedImage for &Path {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        std::fs::read(self).map_err(From::from)
+    }
+}
+
+impl EncodedImage for PathBuf {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        std::fs::read(self).map_err(From::from)
+    }
+}
+
+impl EncodedImage for &str {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        std::fs::read(self).map_err(From::from)
+    }
+}
+
+impl EncodedImage for Vec<u8> {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.clone())
+    }
+}
+
+impl EncodedImage for &[u8] {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+}
+
+#[allow(dead_code, clippy::all)]
 pub(crate) mod tensorflow {
     tonic::include_proto!("tensorflow");
     pub(crate) mod error {
@@ -53,21 +142,49 @@ pub(crate) mod tensorflow {
     }
 }
 
+use batching::{Joined, MicroBatcher, Pending};
+use cache::{MetadataCache, ResultCache};
+use inflight::InFlight;
+use preprocessing::ImageOptions;
+use retry::Backpressure;
+use service::PredictionService;
 use tensorflow::tensorflow_serving::{
     input, model_server_config, model_service_client::ModelServiceClient,
-    model_spec::VersionChoice, prediction_service_client::PredictionServiceClient,
-    ClassificationRequest, ClassificationResult, ExampleList, GetModelMetadataRequest,
-    GetModelMetadataResponse, GetModelStatusRequest, GetModelStatusResponse, Input,
-    ModelConfigList, ModelServerConfig, ModelSpec, PredictRequest, PredictResponse,
-    ReloadConfigRequest, ReloadConfigResponse,
+    model_spec::VersionChoice, model_version_status::State, ClassificationRequest, ExampleList,
+    ExampleListWithContext, GetModelMetadataRequest, GetModelMetadataResponse,
+    GetModelStatusRequest, GetModelStatusResponse, InferenceTask, Input, ModelSpec,
+    MultiInferenceRequest, PredictRequest, RegressionRequest, ReloadConfigRequest,
+    ReloadConfigResponse,
 };
 use tensorflow::{
-    feature, feature::Kind, tensor_shape_proto, BytesList, Example, Feature, Features, FloatList,
-    Int64List, TensorProto, TensorShapeProto,
+    feature::Kind, tensor_shape_proto, BytesList, Example, Feature, Features, FloatList, Int64List,
 };
 
 // Re-exports
-pub use tensorflow::tensorflow_serving::ModelConfig;
+pub use any::{AnyDecoder, AnyRegistry};
+pub use benchmark::BenchmarkReport;
+pub use classification::{classifications, scored_classes, Classification};
+pub use embedding::{cosine_similarity, l2_normalize, Embedding};
+pub use error::{InputError, TensorServingError, TensorflowServingError};
+pub use inference::{Task, TaskType};
+pub use inputs::{build_inputs, InputValue};
+pub use observer::{Observer, RequestStats};
+pub use prediction::{OutputKeys, PredictionBatch, PredictionDiff, PredictionResult};
+pub use preprocessing::{image_to_tensor, Channels, Layout, NonFinitePolicy};
+pub use retry::{Jitter, RetryPolicy};
+pub use stats::{ChannelStats, InputStats};
+pub use status::VersionPolicy;
+pub use tensor::{
+    as_complex64, as_floats, as_int16, as_int64s, as_string_vec, as_uint16, as_utf8_strings,
+    as_utf8_strings_lossy, pack_tensor_content, squeeze_batch, NestedTensor,
+};
+pub use tensorflow::tensorflow_serving::{
+    inference_result, Class, ClassificationResult, Classifications, InferenceResult, ModelConfig,
+    ModelConfigList, ModelServerConfig, ModelVersionStatus, MultiInferenceResponse,
+    PredictResponse, RegressionResult, SignatureDefMap,
+};
+pub use tensorflow::{DataType, SignatureDef, TensorInfo, TensorProto, TensorShapeProto};
+pub use tiling::{stitch_tiles, Tile};
 
 /// Builder pattern used to build the client.
 ///
@@ -87,7 +204,33 @@ pub use tensorflow::tensorflow_serving::ModelConfig;
 pub struct TensorflowServingBuilder {
     hostname: Option<String>,
     port: Option<u16>,
+    replicas: Vec<(String, u16)>,
     signature_name: Option<String>,
+    input_tensor_name: Option<String>,
+    encoded_tensor_name: Option<String>,
+    labels_file: Option<PathBuf>,
+    observer: Option<Observer>,
+    validate_inputs: bool,
+    request_id: Option<String>,
+    metadata_cache_ttl: Option<Duration>,
+    validate_signature_on_connect: Option<String>,
+    retry_policy: RetryPolicy,
+    output_keys: Option<OutputKeys>,
+    infer_output_keys: bool,
+    infer_input_dtype: bool,
+    image_options: ImageOptions,
+    squeeze_batch: bool,
+    allow_duplicates: bool,
+    result_cache_capacity: Option<usize>,
+    tcp_nodelay: Option<bool>,
+    tcp_keepalive: Option<Duration>,
+    micro_batch: Option<(Duration, usize)>,
+    min_version: Option<i64>,
+    tensor_content: bool,
+    attach_input_checksum: bool,
+    prediction_service: Option<String>,
+    #[cfg(feature = "tls")]
+    tls: tls::TlsOptions,
 }
 
 impl TensorflowServingBuilder {
@@ -105,6 +248,19 @@ impl TensorflowServingBuilder {
         self
     }
 
+    /// Add another server replica to balance calls between
+    ///
+    /// The server given with [hostname](#method.hostname) and [port](#method.port) is the
+    /// first replica, and each call to `replica` appends one more, so replica `n` in
+    /// [predict_on](struct.TensorflowServing.html#method.predict_on) is the `n`th added here.
+    /// With any replicas added, calls are load balanced across all of them, and connections
+    /// are made lazily, so a replica being down shows up as an error on the calls routed to
+    /// it rather than when the client is built. All replicas share the other options.
+    pub fn replica<S: Into<String>>(&mut self, hostname: S, port: u16) -> &mut Self {
+        self.replicas.push((hostname.into(), port));
+        self
+    }
+
     /// Set the signature name
     ///
     pub fn signature_name<S: Into<String>>(&mut self, signature_name: S) -> &mut Self {
@@ -112,59 +268,813 @@ impl TensorflowServingBuilder {
         self
     }
 
+    /// Set the name of the input tensor for single input predictions
+    ///
+    /// Image and numeric predictions, e.g. [predict](struct.TensorflowServing.html#method.predict),
+    /// send their tensor under this name, which must match the input name of the model
+    /// signature. Defaults to "input", while many SavedModels use names such as "input_1"
+    /// or "images".
+    pub fn input_tensor_name<S: Into<String>>(&mut self, input_tensor_name: S) -> &mut Self {
+        self.input_tensor_name = Some(input_tensor_name.into());
+        self
+    }
+
+    /// Set the name of the input tensor for encoded image predictions
+    ///
+    /// [predict_encoded](struct.TensorflowServing.html#method.predict_encoded) sends the
+    /// encoded bytes under this name, which defaults to "image_bytes" as used by e.g. the
+    /// resnet serving example.
+    pub fn encoded_tensor_name<S: Into<String>>(&mut self, encoded_tensor_name: S) -> &mut Self {
+        self.encoded_tensor_name = Some(encoded_tensor_name.into());
+        self
+    }
+
+    /// Load class labels from a newline delimited file
+    ///
+    /// Line N of the file holds the label for class N. The file is read once when the client
+    /// is built, and the labels are attached to every `PredictionResult` so that
+    /// [`PredictionResult::label`](struct.PredictionResult.html#method.label) can be used.
+    ///
+    /// Tensorflow Serving does not expose SavedModel assets over its API, so label files
+    /// shipped as model assets must be made available to the client separately.
+    pub fn with_labels_file<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.labels_file = Some(path.into());
+        self
+    }
+
+    /// Set a callback to observe every request
+    ///
+    /// The callback is called with the encoded request and response sizes, and the time taken,
+    /// once each request completes successfully. The same information is logged at debug level.
+    pub fn observer<F>(&mut self, observer: F) -> &mut Self
+    where
+        F: Fn(&RequestStats) + Send + Sync + 'static,
+    {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Validate prediction inputs against the model signature
+    ///
+    /// When enabled, the model metadata is fetched before each prediction and the names of
+    /// the provided input tensors are checked against the inputs of the signature, returning
+    /// an [`InputError::InputMismatch`](enum.InputError.html) listing any differences. This
+    /// costs an extra round trip per prediction, so defaults to off.
+    pub fn validate_inputs(&mut self, validate_inputs: bool) -> &mut Self {
+        self.validate_inputs = validate_inputs;
+        self
+    }
+
+    /// Set the default request ID
+    ///
+    /// The request ID is sent as `x-request-id` metadata on every call, and included in the
+    /// logs and [`RequestStats`](struct.RequestStats.html), so requests can be correlated with
+    /// Tensorflow Serving logs. Use
+    /// [with_request_id](struct.TensorflowServing.html#method.with_request_id) to override it
+    /// for individual calls.
+    pub fn request_id<S: Into<String>>(&mut self, request_id: S) -> &mut Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Cache model metadata for the given duration
+    ///
+    /// Metadata is cached per model name and version, and used for signature lookups and
+    /// [`model_metadata`](struct.TensorflowServing.html#method.model_metadata) calls. By
+    /// default metadata is not cached. Use
+    /// [invalidate_metadata](struct.TensorflowServing.html#method.invalidate_metadata) to drop
+    /// cached metadata when a new model version is pushed.
+    pub fn metadata_cache_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.metadata_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Check the signature exists when the client is built
+    ///
+    /// Fetches the metadata of `model_name` in `build`, and fails if the configured signature
+    /// is not present, listing the signatures which are available. This turns a bad signature
+    /// name into a startup failure rather than an error on the first request.
+    pub fn validate_signature_on_connect<S: Into<String>>(&mut self, model_name: S) -> &mut Self {
+        self.validate_signature_on_connect = Some(model_name.into());
+        self
+    }
+
+    /// Retry calls which fail because the connection to the server was lost
+    ///
+    /// Whatever the policy, a client whose connection breaks re-establishes it on the next
+    /// call rather than failing forever, so long-lived clients survive server restarts. The
+    /// policy controls how many times the failed call itself is retried. See
+    /// [`RetryPolicy`](struct.RetryPolicy.html) for details.
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Names of the required fields which have not been set
+    ///
+    /// This allows configuration to be validated without attempting a connection. `build`
+    /// fails if any fields are missing.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::TensorflowServing;
+    /// let mut builder = TensorflowServing::new();
+    /// builder.hostname("localhost");
+    /// assert_eq!(builder.missing_fields(), vec!["port"]);
+    /// ```
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.hostname.is_none() {
+            missing.push("hostname");
+        }
+        if self.port.is_none() {
+            missing.push("port");
+        }
+        missing
+    }
+
+    /// Set the names of the outputs used to build a `PredictionResult`
+    ///
+    /// Overrides the default `probabilities` and `classes` names, and any names inferred with
+    /// [infer_output_keys](#method.infer_output_keys).
+    pub fn output_keys(&mut self, output_keys: OutputKeys) -> &mut Self {
+        self.output_keys = Some(output_keys);
+        self
+    }
+
+    /// Infer the names of the outputs used to build a `PredictionResult` from the signature
+    ///
+    /// When enabled, the model metadata is fetched for each prediction and the output names
+    /// are picked as described in
+    /// [`OutputKeys::from_signature`](struct.OutputKeys.html#method.from_signature). Use
+    /// [metadata_cache_ttl](#method.metadata_cache_ttl) to avoid the extra round trip. If the
+    /// metadata cannot be fetched the default names are used.
+    pub fn infer_output_keys(&mut self, infer_output_keys: bool) -> &mut Self {
+        self.infer_output_keys = infer_output_keys;
+        self
+    }
+
+    /// Pick the image tensor data type for predictions from the signature
+    ///
+    /// When enabled, the model metadata is fetched for each
+    /// [predict](struct.TensorflowServing.html#method.predict) call, and the image is sent to
+    /// match the data type of the signature's input tensor:
+    ///
+    /// * `DT_UINT8`, as used by quantized models: the raw 8-bit pixel values, 0-255, with
+    ///   16-bit images scaled down to the same range
+    /// * `DT_UINT16`, as used by some medical and remote sensing models: the raw 16-bit
+    ///   pixel values, 0-65535, with 8-bit images scaled up to the same range
+    /// * `DT_FLOAT`: the pixel values scaled to 0-1, i.e. divided by 255 for 8-bit images
+    ///   and by 65535 for 16-bit images
+    ///
+    /// This is the only way [predict](struct.TensorflowServing.html#method.predict) sends
+    /// `DT_UINT16` tensors, even for 16-bit images; otherwise they are sent as floats holding
+    /// the 16-bit values. [image_to_tensor](fn.image_to_tensor.html) with
+    /// `DataType::DtUint16` builds such a tensor directly, for
+    /// [predict_inputs](struct.TensorflowServing.html#method.predict_inputs).
+    ///
+    /// Models expecting other scalings, e.g. -1 to 1, need
+    /// [predict_with_preprocessing][preprocessing] instead. Values normalized with
+    /// [normalize](#method.normalize) cannot be sent as `DT_UINT8` or `DT_UINT16`, so such
+    /// predictions return an [`InputError::NormalizedIntegerInput`](enum.InputError.html).
+    /// For other data types, or if the metadata cannot be fetched, the image is sent as
+    /// unscaled floats as usual. Use [metadata_cache_ttl](#method.metadata_cache_ttl) to avoid
+    /// the extra round trip.
+    ///
+    /// [preprocessing]: struct.TensorflowServing.html#method.predict_with_preprocessing
+    pub fn infer_input_dtype(&mut self, infer_input_dtype: bool) -> &mut Self {
+        self.infer_input_dtype = infer_input_dtype;
+        self
+    }
+
+    /// Convert images to grayscale before prediction
+    ///
+    /// For models trained on grayscale images. Images are converted to single channel
+    /// luminance before preprocessing, and sent with a channel dimension of 1. A shorthand for
+    /// [channels](#method.channels) with `Channels::Gray`, or `Channels::Rgb` when disabled.
+    pub fn to_grayscale(&mut self, to_grayscale: bool) -> &mut Self {
+        self.image_options.channels = if to_grayscale {
+            Channels::Gray
+        } else {
+            Channels::Rgb
+        };
+        self
+    }
+
+    /// Convert images to the given channels before prediction
+    ///
+    /// Images are converted before preprocessing, whatever their source pixel format, and the
+    /// channel dimension of the tensor is set to match: 3 for `Channels::Rgb`, the default, 1
+    /// for `Channels::Gray` and 4 for `Channels::Rgba`, e.g. for models which use the alpha
+    /// channel as a mask.
+    pub fn channels(&mut self, channels: Channels) -> &mut Self {
+        self.image_options.channels = channels;
+        self
+    }
+
+    /// Downscale images larger than `max_input_dimension` pixels before prediction
+    ///
+    /// A safety valve against huge images exceeding the gRPC message size limit. Images whose
+    /// width or height exceeds the limit are downscaled, preserving their aspect ratio, so the
+    /// longest side is `max_input_dimension` pixels. Downscaling is logged at info level.
+    /// Smaller images are sent unchanged.
+    pub fn max_input_dimension(&mut self, max_input_dimension: u32) -> &mut Self {
+        self.image_options.max_dimension = Some(max_input_dimension);
+        self
+    }
+
+    /// Resize every image to `width` by `height` pixels before prediction
+    ///
+    /// Most image models take a fixed input size, e.g. 224x224. Images are resized exactly,
+    /// without preserving the aspect ratio, using `filter`, and the tensor shape is set from
+    /// the resized image:
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # use image::imageops::FilterType;
+    /// let tensor = TensorflowServing::new()
+    ///     .resize_to(224, 224, FilterType::CatmullRom)
+    ///     .image_tensor(image::DynamicImage::new_rgb8(500, 300), |p| p / 255.0)
+    ///     .unwrap();
+    /// let dims: Vec<i64> = tensor.tensor_shape.unwrap().dim.iter().map(|d| d.size).collect();
+    /// assert_eq!(dims, vec![1, 224, 224, 3]);
+    /// ```
+    ///
+    /// Images are resized after [max_input_dimension](#method.max_input_dimension) is
+    /// applied, and before [input_shape](#method.input_shape).
+    pub fn resize_to(&mut self, width: u32, height: u32, filter: FilterType) -> &mut Self {
+        self.image_options.resize = Some((width, height, filter));
+        self
+    }
+
+    /// Resize every image to a fixed `[height, width, channels]` shape before prediction
+    ///
+    /// For models with a fixed input size, e.g. `[224, 224, 3]`, so images can be passed to
+    /// [predict](struct.TensorflowServing.html#method.predict) at any size. Images are
+    /// resized to exactly `height` by `width`, without preserving the aspect ratio. The
+    /// configured shape is used as given, rather than any shape from the model signature.
+    ///
+    /// Images are sent with the number of [channels](#method.channels) configured, 3 by
+    /// default, and a prediction returns an
+    /// [`InputError::ShapeMismatch`](enum.InputError.html) if `channels` does not match.
+    pub fn input_shape(&mut self, input_shape: [u32; 3]) -> &mut Self {
+        self.image_options.input_shape = Some(input_shape);
+        self
+    }
+
+    /// Arrange image tensors in the given memory layout
+    ///
+    /// Defaults to `Layout::Nhwc`, `[1, height, width, channels]`, as used by Tensorflow.
+    /// Models converted from other frameworks, e.g. PyTorch via ONNX, often expect
+    /// `Layout::Nchw`, `[1, channels, height, width]`, for which the values are reordered so
+    /// each channel is contiguous:
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{Layout, TensorflowServing};
+    /// let img = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(3, 1, |x, _| {
+    ///     image::Rgb([x as u8, 10 + x as u8, 20 + x as u8])
+    /// }));
+    /// let tensor = TensorflowServing::new()
+    ///     .layout(Layout::Nchw)
+    ///     .image_tensor(img, |p| p)
+    ///     .unwrap();
+    /// // The red values of all three pixels come first
+    /// assert_eq!(&tensor.float_val[..3], &[0.0, 1.0, 2.0]);
+    /// let dims: Vec<i64> = tensor.tensor_shape.unwrap().dim.iter().map(|d| d.size).collect();
+    /// assert_eq!(dims, vec![1, 3, 1, 3]);
+    /// ```
+    pub fn layout(&mut self, layout: Layout) -> &mut Self {
+        self.image_options.layout = layout;
+        self
+    }
+
+    /// Normalize each channel by its mean and standard deviation before prediction
+    ///
+    /// A preprocessing function sees each value without knowing its channel, so it cannot
+    /// apply the per channel normalization used by e.g. ImageNet models. The values of each
+    /// channel have `mean` subtracted and are divided by `std`, after the preprocessing
+    /// function, so the statistics must be in the same scale as its output:
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::TensorflowServing;
+    /// let tensor = TensorflowServing::new()
+    ///     .normalize([0.485, 0.456, 0.406], [0.229, 0.224, 0.225])
+    ///     .image_tensor(image::DynamicImage::new_rgb8(1, 1), |p| p / 255.0)
+    ///     .unwrap();
+    /// let expected = [-0.485 / 0.229, -0.456 / 0.224, -0.406 / 0.225];
+    /// assert_eq!(tensor.float_val, expected);
+    /// ```
+    ///
+    /// Grayscale images use the first mean and standard deviation, and the alpha channel of
+    /// RGBA images is not normalized.
+    pub fn normalize(&mut self, mean: [f32; 3], std: [f32; 3]) -> &mut Self {
+        self.image_options.normalize = Some((mean, std));
+        self
+    }
+
+    /// Choose how NaN and infinite values produced by preprocessing are handled
+    ///
+    /// Defaults to [`NonFinitePolicy::Error`](enum.NonFinitePolicy.html), which fails the
+    /// prediction before it is sent. [`NonFinitePolicy::Clamp`](enum.NonFinitePolicy.html)
+    /// replaces the values with finite ones instead.
+    pub fn non_finite_policy(&mut self, policy: NonFinitePolicy) -> &mut Self {
+        self.image_options.non_finite = policy;
+        self
+    }
+
+    /// Strip the leading batch dimension from prediction outputs
+    ///
+    /// When enabled, the shape of every output tensor in a prediction response has its first
+    /// dimension removed if it is 1, as happens when a single input is sent (see
+    /// [`squeeze_batch`](fn.squeeze_batch.html)). Outputs whose batch dimension is not 1 are
+    /// left unchanged.
+    pub fn squeeze_batch(&mut self, squeeze_batch: bool) -> &mut Self {
+        self.squeeze_batch = squeeze_batch;
+        self
+    }
+
+    /// Allow feature names to be repeated in classification inputs
+    ///
+    /// By default, giving the same feature name more than once returns an
+    /// [`InputError::DuplicateFeature`](enum.InputError.html), to catch misconfigured feature
+    /// lists. When enabled, the last value given for a name is used instead.
+    pub fn allow_duplicates(&mut self, allow_duplicates: bool) -> &mut Self {
+        self.allow_duplicates = allow_duplicates;
+        self
+    }
+
+    /// Cache up to `capacity` prediction results for repeated identical inputs
+    ///
+    /// Results are keyed by a 128-bit digest of the encoded model spec and input tensors, so the
+    /// cache does not keep copies of the inputs, and the least recently used results are evicted
+    /// once the cache is full.
+    /// Cached results are returned without contacting the server, so this is only safe for
+    /// deterministic models, and for a fixed model version, as results from an old version
+    /// are returned even after a new one has been loaded. The cache applies to predictions
+    /// returning a `PredictionResult`, and is shared between clones of the client. By default
+    /// nothing is cached.
+    pub fn result_cache(&mut self, capacity: usize) -> &mut Self {
+        self.result_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Set `TCP_NODELAY` on the connection to the server
+    ///
+    /// Disabling Nagle's algorithm lowers latency for workloads sending single small requests.
+    pub fn tcp_nodelay(&mut self, tcp_nodelay: bool) -> &mut Self {
+        self.tcp_nodelay = Some(tcp_nodelay);
+        self
+    }
+
+    /// Send TCP keepalive probes on the connection after it has been idle for `keepalive`
+    pub fn tcp_keepalive(&mut self, keepalive: Duration) -> &mut Self {
+        self.tcp_keepalive = Some(keepalive);
+        self
+    }
+
+    /// Coalesce single predictions into batched requests
+    ///
+    /// Predictions returning a `PredictionResult` join a queue instead of being sent straight
+    /// away. The queue is sent as one request, with the inputs concatenated along the batch
+    /// dimension, once `max_delay` has passed since the first prediction joined it, or once
+    /// `max_size` predictions have joined, whichever comes first. The outputs are split back
+    /// between the predictions, so each call sees the same result as if it had been sent on
+    /// its own. This lets the server batch bursty single image traffic, at the cost of up to
+    /// `max_delay` of extra latency per call.
+    ///
+    /// Only predictions for the same model, with inputs of the same names, data types and
+    /// shapes, and with the same request ID and call metadata, are batched together. The queue is shared between clones of the client, and is
+    /// sent by the call which opened it, so dropping that call before it completes fails the
+    /// other calls in its batch. Queued predictions count as in flight for
+    /// [shutdown](struct.TensorflowServing.html#method.shutdown). If the batched request fails, every call in the batch
+    /// returns the error.
+    pub fn micro_batch(&mut self, max_delay: Duration, max_size: usize) -> &mut Self {
+        self.micro_batch = Some((max_delay, max_size));
+        self
+    }
+
+    /// Refuse predictions served by a model version older than `min_version`
+    ///
+    /// Tensorflow Serving reports the version which served each prediction, and when it is
+    /// lower than `min_version` the prediction returns an error instead of its outputs. This
+    /// guards against routing traffic to a stale model, e.g. after a known-bad version was
+    /// restored during a botched rollout. Responses without a version are accepted.
+    pub fn min_version(&mut self, min_version: i64) -> &mut Self {
+        self.min_version = Some(min_version);
+        self
+    }
+
+    /// Send input tensors as packed `tensor_content` bytes
+    ///
+    /// When enabled, numeric input tensors are sent with their values packed little-endian in
+    /// `tensor_content` (see [`pack_tensor_content`](fn.pack_tensor_content.html)) rather
+    /// than in the typed value fields, which is more efficient for large float tensors.
+    /// Responses are accepted in either encoding regardless of this option.
+    pub fn tensor_content(&mut self, tensor_content: bool) -> &mut Self {
+        self.tensor_content = tensor_content;
+        self
+    }
+
+    /// Send a CRC32 checksum of the input tensors with every prediction
+    ///
+    /// When enabled, each predict request carries `x-input-crc32` metadata: the CRC32 of the
+    /// protobuf encoded input tensors, concatenated in input name order, as 8 lowercase hex
+    /// digits. For a single input this is the checksum of the encoded `TensorProto`. A proxy or
+    /// server can recompute it to detect tensors corrupted in transit.
+    pub fn attach_input_checksum(&mut self, attach_input_checksum: bool) -> &mut Self {
+        self.attach_input_checksum = attach_input_checksum;
+        self
+    }
+
+    /// Call the prediction service under a different fully qualified name
+    ///
+    /// For servers derived from Tensorflow Serving which expose the prediction service in
+    /// another package, e.g. `mycorp.serving.PredictionService`. The methods and messages
+    /// must be the same as the standard service, only the request paths change, and the model
+    /// service is unaffected. Defaults to `tensorflow.serving.PredictionService`.
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # async fn run() -> tensorflow_serving::Result<()> {
+    /// let mut serving = TensorflowServing::new()
+    ///     .hostname("localhost")
+    ///     .port(8500)
+    ///     .prediction_service("mycorp.serving.PredictionService")
+    ///     .build()
+    ///     .await?;
+    /// // Sent to /mycorp.serving.PredictionService/Predict
+    /// let result = serving.predict("cat.jpg", "resnet").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prediction_service<S: Into<String>>(&mut self, prediction_service: S) -> &mut Self {
+        self.prediction_service = Some(prediction_service.into());
+        self
+    }
+
+    /// Verify the server's certificate against the CA certificate in the PEM file at `path`
+    ///
+    /// Setting any of the `tls_*` options connects to the server over TLS. No system roots
+    /// are loaded, so the CA certificate is needed to verify the server. The files are read
+    /// when the client is built.
+    #[cfg(feature = "tls")]
+    pub fn tls_ca_cert<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.tls.ca_cert = Some(path.into());
+        self
+    }
+
+    /// Authenticate to the server with the client certificate in the PEM file at `path`
+    ///
+    /// For mutual TLS, and must be given along with [tls_client_key](#method.tls_client_key).
+    #[cfg(feature = "tls")]
+    pub fn tls_client_cert<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.tls.client_cert = Some(path.into());
+        self
+    }
+
+    /// Private key for the client certificate, in the PEM file at `path`
+    #[cfg(feature = "tls")]
+    pub fn tls_client_key<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.tls.client_key = Some(path.into());
+        self
+    }
+
+    /// Name to verify the server's certificate against, if it differs from the hostname
+    #[cfg(feature = "tls")]
+    pub fn tls_domain<S: Into<String>>(&mut self, domain: S) -> &mut Self {
+        self.tls.domain = Some(domain.into());
+        self
+    }
+
     /// Build a `TensorflowServing` client.
     ///
     pub async fn build(&mut self) -> Result<TensorflowServing> {
-        if self.hostname.is_none() {
-            return Err("hostname not provided".into());
+        let missing = self.missing_fields();
+        if !missing.is_empty() {
+            return Err(TensorflowServingError::Builder(format!(
+                "{} not provided",
+                missing.join(", ")
+            )));
         }
 
-        if self.port.is_none() {
-            return Err("port not provided".into());
+        let labels = match self.labels_file.take() {
+            Some(path) => Some(Arc::new(prediction::load_labels(path)?)),
+            None => None,
+        };
+
+        let hostname = self.hostname.take().unwrap();
+        let port = self.port.unwrap();
+        let mut endpoints = vec![self.endpoint(&hostname, port)?];
+        for (hostname, port) in std::mem::take(&mut self.replicas) {
+            endpoints.push(self.endpoint(&hostname, port)?);
+        }
+        let prediction_service: Arc<str> = self
+            .prediction_service
+            .as_deref()
+            .unwrap_or(service::PREDICTION_SERVICE)
+            .into();
+        service::method_path(&prediction_service, "Predict")
+            .map_err(TensorflowServingError::Builder)?;
+        let clients = Clients::connect(&endpoints, prediction_service).await?;
+
+        let mut client = self.assemble(clients, endpoints, labels);
+
+        if let Some(model_name) = self.validate_signature_on_connect.take() {
+            client.signature(model_name).await?;
+        }
+
+        Ok(client)
+    }
+
+    /// Endpoint for the server at `hostname` and `port`, with the connection options applied
+    fn endpoint(&self, hostname: &str, port: u16) -> Result<Endpoint> {
+        #[cfg(feature = "tls")]
+        let scheme = if self.tls.is_enabled() {
+            "https"
+        } else {
+            "http"
+        };
+        #[cfg(not(feature = "tls"))]
+        let scheme = "http";
+        let mut endpoint = Endpoint::from_shared(format!("{}://{}:{}", scheme, hostname, port))
+            .map_err(|e| {
+                TensorflowServingError::Builder(format!(
+                    "invalid server address {}:{}: {}",
+                    hostname, port, e
+                ))
+            })?;
+        #[cfg(feature = "tls")]
+        {
+            if self.tls.is_enabled() {
+                endpoint = endpoint.tls_config(self.tls.client_config()?);
+            }
+        }
+        if let Some(tcp_nodelay) = self.tcp_nodelay {
+            endpoint = endpoint.tcp_nodelay(tcp_nodelay);
         }
+        if self.tcp_keepalive.is_some() {
+            endpoint = endpoint.tcp_keepalive(self.tcp_keepalive);
+        }
+        Ok(endpoint)
+    }
 
+    /// Build the client around connected service clients, taking the configured options
+    fn assemble(
+        &mut self,
+        clients: Clients,
+        endpoints: Vec<Endpoint>,
+        labels: Option<Arc<Vec<String>>>,
+    ) -> TensorflowServing {
         let signature_name = self
             .signature_name
             .take()
             .unwrap_or_else(|| "serving_default".to_string());
+        let input_name = self
+            .input_tensor_name
+            .take()
+            .unwrap_or_else(|| "input".to_string());
+        let encoded_input_name = self
+            .encoded_tensor_name
+            .take()
+            .unwrap_or_else(|| "image_bytes".to_string());
+
+        TensorflowServing {
+            clients,
+            endpoints: endpoints.into(),
+            reconnect: false,
+            retry_policy: self.retry_policy.clone(),
+            output_keys: self.output_keys.take(),
+            infer_output_keys: self.infer_output_keys,
+            infer_input_dtype: self.infer_input_dtype,
+            image_options: self.image_options.clone(),
+            squeeze_batch: self.squeeze_batch,
+            allow_duplicates: self.allow_duplicates,
+            in_flight: InFlight::default(),
+            backpressure: Backpressure::default(),
+            signature_name,
+            input_name,
+            encoded_input_name,
+            labels,
+            observer: self.observer.take(),
+            validate_inputs: self.validate_inputs,
+            request_id: self.request_id.take(),
+            call_metadata: None,
+            registry: HashMap::new(),
+            metadata_cache: MetadataCache::new(self.metadata_cache_ttl),
+            result_cache: ResultCache::new(self.result_cache_capacity),
+            micro_batcher: MicroBatcher::new(self.micro_batch),
+            min_version: self.min_version,
+            tensor_content: self.tensor_content,
+            attach_input_checksum: self.attach_input_checksum,
+        }
+    }
 
-        let hostname = self.hostname.take().unwrap();
-        let prediction_client =
-            PredictionServiceClient::connect(format!("http://{}:{}", hostname, self.port.unwrap()))
-                .await?;
+    /// Build the tensor a client with these options sends for an image, without connecting
+    ///
+    /// The configured image options, such as [resize_to](#method.resize_to) and
+    /// [channels](#method.channels), are applied as for
+    /// [predict_with_preprocessing][preprocessing], which is useful for checking
+    /// preprocessing before sending any requests.
+    ///
+    /// [preprocessing]: struct.TensorflowServing.html#method.predict_with_preprocessing
+    pub fn image_tensor<I, M>(&self, img: I, preprocessing_fn: M) -> Result<TensorProto>
+    where
+        I: Image,
+        M: Fn(f32) -> f32,
+    {
+        preprocessing::to_tensor(
+            img,
+            preprocessing_fn,
+            self.image_options.layout,
+            DataType::DtFloat,
+            &self.image_options,
+        )
+    }
+
+    /// Build a blocking `TensorflowServing` client.
+    ///
+    /// See the [`blocking`](blocking/index.html) module for details.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(&mut self) -> Result<blocking::TensorflowServing> {
+        blocking::TensorflowServing::connect(self)
+    }
+}
 
-        let model_client =
-            ModelServiceClient::connect(format!("http://{}:{}", hostname, self.port.unwrap()))
-                .await?;
+/// Shown in place of values which may hold credentials, such as request IDs and metadata
+struct Redacted;
 
-        Ok(TensorflowServing {
-            prediction_client,
-            model_client,
-            signature_name,
-        })
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Debug output for metadata which shows the keys, but redacts the values
+struct RedactedMetadata<'a>(&'a MetadataMap);
+
+impl fmt::Debug for RedactedMetadata<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers = self.0.clone().into_headers();
+        f.debug_map()
+            .entries(headers.keys().map(|key| (key.as_str(), Redacted)))
+            .finish()
+    }
+}
+
+impl fmt::Debug for TensorflowServingBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("TensorflowServingBuilder");
+        f.field("hostname", &self.hostname)
+            .field("port", &self.port)
+            .field("replicas", &self.replicas)
+            .field("signature_name", &self.signature_name)
+            .field("input_tensor_name", &self.input_tensor_name)
+            .field("encoded_tensor_name", &self.encoded_tensor_name)
+            .field("labels_file", &self.labels_file)
+            .field("observer", &self.observer.is_some())
+            .field("validate_inputs", &self.validate_inputs)
+            .field("request_id", &self.request_id.as_ref().map(|_| Redacted))
+            .field("metadata_cache_ttl", &self.metadata_cache_ttl)
+            .field(
+                "validate_signature_on_connect",
+                &self.validate_signature_on_connect,
+            )
+            .field("retry_policy", &self.retry_policy)
+            .field("output_keys", &self.output_keys)
+            .field("infer_output_keys", &self.infer_output_keys)
+            .field("infer_input_dtype", &self.infer_input_dtype)
+            .field("image_options", &self.image_options)
+            .field("squeeze_batch", &self.squeeze_batch)
+            .field("allow_duplicates", &self.allow_duplicates)
+            .field("result_cache_capacity", &self.result_cache_capacity)
+            .field("tcp_nodelay", &self.tcp_nodelay)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field("micro_batch", &self.micro_batch)
+            .field("min_version", &self.min_version)
+            .field("tensor_content", &self.tensor_content)
+            .field("attach_input_checksum", &self.attach_input_checksum)
+            .field("prediction_service", &self.prediction_service);
+        #[cfg(feature = "tls")]
+        f.field("tls", &self.tls);
+        f.finish()
     }
 }
 
 impl Clone for TensorflowServing {
     fn clone(&self) -> Self {
         Self {
-            prediction_client: self.prediction_client.clone(),
-            model_client: self.model_client.clone(),
+            clients: self.clients.clone(),
+            endpoints: self.endpoints.clone(),
+            reconnect: self.reconnect,
+            retry_policy: self.retry_policy.clone(),
+            output_keys: self.output_keys.clone(),
+            infer_output_keys: self.infer_output_keys,
+            infer_input_dtype: self.infer_input_dtype,
+            image_options: self.image_options.clone(),
+            squeeze_batch: self.squeeze_batch,
+            allow_duplicates: self.allow_duplicates,
+            in_flight: self.in_flight.clone(),
+            backpressure: self.backpressure.clone(),
             signature_name: self.signature_name.clone(),
+            input_name: self.input_name.clone(),
+            encoded_input_name: self.encoded_input_name.clone(),
+            labels: self.labels.clone(),
+            observer: self.observer.clone(),
+            validate_inputs: self.validate_inputs,
+            request_id: self.request_id.clone(),
+            call_metadata: self.call_metadata.clone(),
+            registry: self.registry.clone(),
+            metadata_cache: self.metadata_cache.clone(),
+            result_cache: self.result_cache.clone(),
+            micro_batcher: self.micro_batcher.clone(),
+            min_version: self.min_version,
+            tensor_content: self.tensor_content,
+            attach_input_checksum: self.attach_input_checksum,
         }
     }
 }
 
+impl fmt::Debug for TensorflowServing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TensorflowServing")
+            .field("endpoints", &self.endpoints)
+            .field("retry_policy", &self.retry_policy)
+            .field("output_keys", &self.output_keys)
+            .field("infer_output_keys", &self.infer_output_keys)
+            .field("infer_input_dtype", &self.infer_input_dtype)
+            .field("image_options", &self.image_options)
+            .field("squeeze_batch", &self.squeeze_batch)
+            .field("allow_duplicates", &self.allow_duplicates)
+            .field("backpressure", &self.backpressure)
+            .field("signature_name", &self.signature_name)
+            .field("input_name", &self.input_name)
+            .field("encoded_input_name", &self.encoded_input_name)
+            .field("labels", &self.labels.as_ref().map(|l| l.len()))
+            .field("observer", &self.observer.is_some())
+            .field("validate_inputs", &self.validate_inputs)
+            .field("request_id", &self.request_id.as_ref().map(|_| Redacted))
+            .field(
+                "call_metadata",
+                &self.call_metadata.as_ref().map(RedactedMetadata),
+            )
+            .field("registry", &self.registry)
+            .field("metadata_cache", &self.metadata_cache)
+            .field("result_cache", &self.result_cache)
+            .field("micro_batcher", &self.micro_batcher)
+            .field("min_version", &self.min_version)
+            .field("tensor_content", &self.tensor_content)
+            .field("attach_input_checksum", &self.attach_input_checksum)
+            .finish()
+    }
+}
+
 /// Tensorflow Serving client
 ///
 /// Used to talk to a Tensorflow Serving server.
 ///
+/// The client is `Send + Sync`, and so are the futures returned by its methods, so it can be
+/// used from spawned tasks. Clones share the underlying channel and caches, which is the
+/// cheapest way to use one client from many tasks. Methods take `&mut self`, so a single
+/// client shared as `Arc<Mutex<_>>` needs an async mutex such as `tokio::sync::Mutex`, as the
+/// lock is held across `.await`; see the `shared` example.
+///
 pub struct TensorflowServing {
-    prediction_client: PredictionServiceClient<tonic::transport::Channel>,
-    model_client: ModelServiceClient<tonic::transport::Channel>,
+    clients: Clients,
+    endpoints: Arc<[Endpoint]>,
+    reconnect: bool,
+    retry_policy: RetryPolicy,
+    output_keys: Option<OutputKeys>,
+    infer_output_keys: bool,
+    infer_input_dtype: bool,
+    image_options: ImageOptions,
+    squeeze_batch: bool,
+    allow_duplicates: bool,
+    in_flight: InFlight,
+    backpressure: Backpressure,
     signature_name: String,
+    input_name: String,
+    encoded_input_name: String,
+    labels: Option<Arc<Vec<String>>>,
+    observer: Option<Observer>,
+    validate_inputs: bool,
+    request_id: Option<String>,
+    call_metadata: Option<MetadataMap>,
+    registry: HashMap<String, ModelDescription<String>>,
+    metadata_cache: MetadataCache,
+    result_cache: ResultCache,
+    micro_batcher: MicroBatcher,
+    min_version: Option<i64>,
+    tensor_content: bool,
+    attach_input_checksum: bool,
 }
 
+// Sharing the client between tasks is part of the public API, so fail the build if a field
+// ever stops it being `Send + Sync`
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<TensorflowServing>();
+    assert_send_sync::<TensorflowServingBuilder>();
+};
+
 impl TensorflowServing {
     /// Construct a new `TensorflowServing` builder struct.
     ///
@@ -173,32 +1083,143 @@ impl TensorflowServing {
         TensorflowServingBuilder::default()
     }
 
+    /// Construct a client around an existing channel
+    ///
+    /// An escape hatch for channels configured outside of the builder, e.g. with a custom
+    /// connector or interceptors. All other options take their defaults. The channel is used
+    /// as-is, so the client does not re-establish it after transport failures.
+    pub fn from_channel<S: Into<String>>(channel: Channel, signature_name: S) -> TensorflowServing {
+        TensorflowServingBuilder::default()
+            .signature_name(signature_name)
+            .assemble(
+                Clients::new(channel, service::PREDICTION_SERVICE.into()),
+                Vec::new(),
+                None,
+            )
+    }
+
+    /// Register a model under an alias
+    ///
+    /// Registered models can be used by alias with [predict_as](#method.predict_as), so
+    /// application code can refer to models by name rather than repeating the model name,
+    /// version and signature. Registering an existing alias replaces it.
+    pub fn register<A, S, F>(&mut self, alias: A, model_description: S) -> &mut Self
+    where
+        A: Into<String>,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        self.registry
+            .insert(alias.into(), model_description.into().into_owned());
+        self
+    }
+
+    /// Run a prediction using a registered model
+    ///
+    /// Returns an [`InputError::UnknownAlias`](enum.InputError.html) if no model has been
+    /// registered under `alias`.
+    pub async fn predict_as<I>(&mut self, alias: &str, img: I) -> Result<PredictionResult>
+    where
+        I: Image,
+    {
+        let description = self
+            .registry
+            .get(alias)
+            .cloned()
+            .ok_or_else(|| InputError::UnknownAlias(alias.to_string()))?;
+        self.predict(img, description).await
+    }
+
     /// Run a classification on a supplied image
     ///
-    pub async fn classify<S, T, F, V>(
+    /// The features are given as `(name, payload)` pairs, e.g. a `HashMap` or a `Vec` of
+    /// pairs. Repeated names are an error unless
+    /// [allow_duplicates](struct.TensorflowServingBuilder.html#method.allow_duplicates) is set.
+    ///
+    /// The result holds the classes and scores of each example, e.g. as `(label, score)`
+    /// pairs with [scored_classes](fn.scored_classes.html). Returns an error if the response
+    /// does not contain a result.
+    pub async fn classify<S, P, T, F, V>(
         &mut self,
         model_name: S,
-        payload_map: HashMap<T, V>,
+        payload_map: P,
     ) -> Result<ClassificationResult>
     where
         S: Into<ModelDescription<F>>,
         F: Into<String>,
+        P: IntoIterator<Item = (T, V)>,
         T: Into<String>,
         V: Into<Payload>,
     {
         let req = ClassificationRequest {
             model_spec: Some(self.build_model_spec(model_name)),
-            input: Some(self.build_input(payload_map)),
+            input: Some(self.build_input(payload_map)?),
         };
 
-        let resp = self.prediction_client.classify(req).await?;
-        unimplemented!("{:#?}", resp)
+        let resp = self
+            .call("classify", req, |mut c, r| async move {
+                c.prediction.classify(r).await
+            })
+            .await?;
 
-        /*
-        let resp = self.prediction_client.classify(&req)?;
-        // TODO: remove this unwrap
-        Ok(resp.result.unwrap())
-        */
+        Ok(resp
+            .result
+            .ok_or("classification result missing from response")?)
+    }
+
+    /// Run a classification for a ranking model
+    ///
+    /// Ranking and recommendation models take one context example (e.g. user features)
+    /// shared by many candidate examples (e.g. items). The context and candidate feature maps
+    /// are sent as an `ExampleListWithContext`, and the classes and scores for each
+    /// candidate are returned in the same order as `candidate_maps`.
+    pub async fn classify_ranked<S, F, T, V>(
+        &mut self,
+        model_name: S,
+        context_map: HashMap<T, V>,
+        candidate_maps: Vec<HashMap<T, V>>,
+    ) -> Result<Vec<Vec<(String, f32)>>>
+    where
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+        T: Into<String>,
+        V: Into<Payload>,
+    {
+        let n_candidates = candidate_maps.len();
+        let example_list = ExampleListWithContext {
+            examples: candidate_maps
+                .into_iter()
+                .map(|m| self.build_example(m))
+                .collect::<Result<_>>()?,
+            context: Some(self.build_example(context_map)?),
+        };
+
+        let req = ClassificationRequest {
+            model_spec: Some(self.build_model_spec(model_name)),
+            input: Some(Input {
+                kind: Some(input::Kind::ExampleListWithContext(example_list)),
+            }),
+        };
+
+        let resp = self
+            .call("classify", req, |mut c, r| async move {
+                c.prediction.classify(r).await
+            })
+            .await?;
+
+        let result = resp
+            .result
+            .ok_or("classification result missing from response")?;
+        if result.classifications.len() != n_candidates {
+            return Err(format!(
+                "expected {} classifications, one per candidate, got {}",
+                n_candidates,
+                result.classifications.len()
+            )
+            .into());
+        }
+
+        Ok(scored_classes(&result))
     }
 
     /// Run a prediction for a supplied image
@@ -209,225 +1230,1638 @@ impl TensorflowServing {
     ///
     /// The `preprocessing_fn` parameter allows customisation of the pixel values.
     ///
+    /// The result is read from the `probabilities` and `classes` outputs of the response, or
+    /// the outputs configured with
+    /// [output_keys](struct.TensorflowServingBuilder.html#method.output_keys). If either
+    /// output is missing from the response an error is returned.
+    ///
     /// [model-description]: struct.ModelDescription.html
     pub async fn predict_with_preprocessing<I, F, S, M>(
         &mut self,
         img: I,
         model_description: S,
         preprocessing_fn: M,
+    ) -> Result<PredictionResult>
+    where
+        I: Image,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+        M: Fn(f32) -> f32,
+    {
+        let mut inputs = HashMap::new();
+        inputs.insert(
+            self.input_name.clone(),
+            self.image_tensor(img, preprocessing_fn)?,
+        );
+
+        self.predict_result(inputs, model_description.into().into_owned())
+            .await
+    }
+
+    /// Run a prediction against one replica, bypassing load balancing
+    ///
+    /// `endpoint_index` picks the replica: 0 is the server given with
+    /// [hostname](struct.TensorflowServingBuilder.html#method.hostname) and
+    /// [port](struct.TensorflowServingBuilder.html#method.port), and the rest follow in the
+    /// order they were added with
+    /// [replica](struct.TensorflowServingBuilder.html#method.replica). An index out of range
+    /// returns an error, as does any index on a client built with
+    /// [from_channel](#method.from_channel), which has no endpoints. A fresh connection is made
+    /// for the call, so a down replica returns an error rather than being retried, and the
+    /// result cache and micro batching are skipped. This is useful for checking a specific
+    /// host while debugging.
+    pub async fn predict_on<I, F, S>(
+        &mut self,
+        endpoint_index: usize,
+        img: I,
+        model_description: S,
+    ) -> Result<PredictionResult>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let endpoint = self.endpoints.get(endpoint_index).ok_or_else(|| {
+            TensorflowServingError::NoReplicas {
+                index: endpoint_index,
+                replicas: self.endpoints.len(),
+            }
+        })?;
+        let channel = endpoint.connect().await?;
+
+        let mut client = self.clone();
+        client.clients = Clients::new(channel, self.clients.prediction.service());
+        client.reconnect = false;
+        client.result_cache = ResultCache::new(None);
+        client.micro_batcher = MicroBatcher::new(None);
+        client.predict(img, model_description).await
+    }
+
+    /// Run a prediction, sending extra gRPC metadata with the call
+    ///
+    /// Behaves like [predict](struct.TensorflowServing.html#method.predict), but adds
+    /// `metadata` to the request, e.g. a priority header honoured by a proxy in front of the
+    /// server. The metadata only applies to this call, so the call skips the result cache and
+    /// micro batching, and is always sent to the server on its own.
+    ///
+    /// When a key appears both in `metadata` and in the metadata the client sends on every
+    /// call, such as the `x-request-id` set with
+    /// [request_id](struct.TensorflowServingBuilder.html#method.request_id), the values given
+    /// here replace the connection level ones.
+    pub async fn predict_with_metadata<I, F, S>(
+        &mut self,
+        img: I,
+        model_description: S,
+        metadata: MetadataMap,
+    ) -> Result<PredictionResult>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let mut client = self.clone();
+        client.call_metadata = Some(metadata);
+        client.result_cache = ResultCache::new(None);
+        client.micro_batcher = MicroBatcher::new(None);
+        client.predict(img, model_description).await
+    }
+
+    /// Run a prediction, also returning statistics about the input tensor
+    ///
+    /// Behaves like [predict-with-preprocessing](#method.predict_with_preprocessing), but also
+    /// summarises the values of each channel of the tensor sent to the server, after
+    /// preprocessing. This is useful for catching normalisation bugs, e.g. values still in the
+    /// range 0-255 when the model expects 0-1.
+    pub async fn predict_with_stats<I, F, S, M>(
+        &mut self,
+        img: I,
+        model_description: S,
+        preprocessing_fn: M,
+    ) -> Result<(PredictionResult, InputStats)>
+    where
+        I: Image,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+        M: Fn(f32) -> f32,
+    {
+        let tensor = self.image_tensor(img, preprocessing_fn)?;
+        let stats = InputStats::from_tensor(&tensor);
+
+        let mut inputs = HashMap::new();
+        inputs.insert(self.input_name.clone(), tensor);
+
+        let result = self
+            .predict_result(inputs, model_description.into().into_owned())
+            .await?;
+        Ok((result, stats))
+    }
+
+    /// Run a prediction, only returning the named outputs
+    ///
+    /// Behaves like [predict-with-preprocessing](#method.predict_with_preprocessing), but
+    /// populates the request `output_filter` so the server only sends back the outputs
+    /// listed in `output_filter`. This saves bandwidth for models with many large outputs. An
+    /// empty filter returns all outputs. The filter is applied by the server, and the
+    /// response is returned as sent, so it shows whether the server honoured the filter.
+    pub async fn predict_with_output_filter<I, F, S, M, O>(
+        &mut self,
+        img: I,
+        model_description: S,
+        preprocessing_fn: M,
+        output_filter: O,
     ) -> Result<PredictResponse>
     where
-        I: Image,
-        F: Into<String>,
+        I: Image,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+        M: Fn(f32) -> f32,
+        O: Into<Vec<String>>,
+    {
+        let output_filter = output_filter.into();
+
+        let tensor = self.image_tensor(img, preprocessing_fn)?;
+
+        let mut inputs = HashMap::new();
+        inputs.insert(self.input_name.clone(), tensor);
+
+        self.send_predict(inputs, model_description, output_filter)
+            .await
+    }
+
+    /// Run a prediction on an embedding model, returning one named float output
+    ///
+    /// Embedding models, e.g. for face recognition or retrieval, output a float vector rather
+    /// than the classification outputs a `PredictionResult` expects. Only `output_name` is
+    /// requested from the server, and it is returned as a flat vector along with its shape.
+    /// Returns an error if the output is missing or is not a `DT_FLOAT` tensor.
+    pub async fn predict_embedding<I, F, S, O>(
+        &mut self,
+        img: I,
+        model_description: S,
+        output_name: O,
+    ) -> Result<Embedding>
+    where
+        I: Image,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+        O: Into<String>,
+    {
+        let output_name = output_name.into();
+        let response = self
+            .predict_with_output_filter(img, model_description, |p| p, vec![output_name.clone()])
+            .await?;
+        let tensor = response
+            .outputs
+            .get(&output_name)
+            .ok_or_else(|| TensorflowServingError::MissingOutput(output_name.clone()))?;
+        Embedding::from_tensor(tensor)
+    }
+
+    /// Run a prediction for an encoded image
+    ///
+    /// Supply something that implements `EncodedImage` i.e. either a path to an image file or
+    /// the bytes of an encoded image. The bytes are sent verbatim, without decoding, as a
+    /// `DT_STRING` tensor named "image_bytes", or the name set with
+    /// [encoded_tensor_name](struct.TensorflowServingBuilder.html#method.encoded_tensor_name),
+    /// for models which decode images themselves.
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let bytes = std::fs::read("cat.jpg")?;
+    /// let result = serving.predict_encoded(bytes, "resnet").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn predict_encoded<E, F, S>(
+        &mut self,
+        encoded: E,
+        model_description: S,
+    ) -> Result<PredictionResult>
+    where
+        E: EncodedImage,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        let mut inputs = HashMap::new();
+        inputs.insert(self.encoded_input_name.clone(), encoded.to_tensor()?);
+
+        self.predict_result(inputs, model_description.into().into_owned())
+            .await
+    }
+
+    /// Run a prediction with input tensors built by the caller
+    ///
+    /// The most general way to make a prediction, for models of any kind: `inputs` is sent as
+    /// the request inputs, keyed by the input names of the model signature, and the raw
+    /// response is returned. The other prediction methods build their tensors, e.g. from an
+    /// image, and send them the same way. Every input must be named, as an empty name returns
+    /// an [`InputError::EmptyInputName`](enum.InputError.html) before the request is sent.
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::{Payload, TensorflowServing};
+    /// # use std::collections::HashMap;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let mut inputs = HashMap::new();
+    /// let tokens = Payload::Ints(vec![101, 2023, 102]).into_tensor(&[1, 3]);
+    /// inputs.insert("tokens".to_string(), tokens);
+    /// let response = serving.predict_inputs(inputs, "bert").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn predict_inputs<F, S>(
+        &mut self,
+        inputs: HashMap<String, TensorProto>,
+        model_description: S,
+    ) -> Result<PredictResponse>
+    where
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        inputs::validate_names(&inputs)?;
+        self.send_predict(inputs, model_description, Vec::new())
+            .await
+    }
+
+    /// Run a prediction with serialized `tf.train.Example` protos as the input
+    ///
+    /// Some predict signatures, e.g. those exported with a parsing serving input receiver,
+    /// take a batch of serialized examples rather than raw tensors. Each feature map is built
+    /// into an `Example` as for [classify](#method.classify), serialized, and the batch is sent
+    /// as a one dimensional `DT_STRING` tensor named `input_name`.
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::{Payload, TensorflowServing};
+    /// # use std::collections::HashMap;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let mut example = HashMap::new();
+    /// example.insert("age".to_string(), Payload::Ints(vec![42]));
+    /// example.insert("income".to_string(), Payload::Floats(vec![52_000.0]));
+    /// let response = serving.predict_examples(vec![example], "examples", "census").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn predict_examples<N, F, S>(
+        &mut self,
+        examples: Vec<HashMap<String, Payload>>,
+        input_name: N,
+        model_description: S,
+    ) -> Result<PredictResponse>
+    where
+        N: Into<String>,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        let serialized = examples
+            .into_iter()
+            .map(|features| {
+                let example = self.build_example(features)?;
+                let mut buf = Vec::with_capacity(example.encoded_len());
+                example
+                    .encode(&mut buf)
+                    .expect("encoding to a Vec cannot fail");
+                Ok(buf)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let dims = [serialized.len() as i64];
+        let mut inputs = HashMap::new();
+        inputs.insert(
+            input_name.into(),
+            Payload::Bytes(serialized).into_tensor(&dims),
+        );
+        self.predict_inputs(inputs, model_description).await
+    }
+
+    /// Run a prediction with several named input tensors
+    ///
+    /// Multi-input models, e.g. taking an image together with a vector of metadata, need
+    /// several tensors in a single request. Build each tensor separately with the helpers, e.g.
+    /// [`image_to_tensor`](fn.image_to_tensor.html) for images,
+    /// [`NestedTensor::to_tensor`](trait.NestedTensor.html#method.to_tensor) for numeric
+    /// values or [`Payload::into_tensor`](enum.Payload.html#method.into_tensor) for flat
+    /// values, and combine them keyed by the input names of the model signature:
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::{image_to_tensor, DataType, Layout};
+    /// # use tensorflow_serving::{NestedTensor, TensorflowServing};
+    /// # use std::collections::HashMap;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let mut inputs = HashMap::new();
+    /// inputs.insert(
+    ///     "image".to_string(),
+    ///     image_to_tensor("cat.jpg", |p| p / 255.0, Layout::Nhwc, DataType::DtFloat)?,
+    /// );
+    /// inputs.insert("metadata".to_string(), vec![vec![0.5f32, 1.0]].to_tensor()?);
+    /// let response = serving.predict_multi(inputs, "resnet").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [build_inputs](fn.build_inputs.html) builds the map from a mix of images and plain
+    /// values in one step. This is equivalent to [predict_inputs](#method.predict_inputs).
+    ///
+    /// The raw response is returned, as multi-input models rarely follow the standard
+    /// classification outputs.
+    pub async fn predict_multi<F, S>(
+        &mut self,
+        inputs: HashMap<String, TensorProto>,
+        model_description: S,
+    ) -> Result<PredictResponse>
+    where
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        self.predict_inputs(inputs, model_description).await
+    }
+
+    /// Send a pre-encoded `PredictRequest`
+    ///
+    /// `bytes` is decoded as a protobuf `PredictRequest` and forwarded unchanged, which is
+    /// useful for reproducing requests captured elsewhere, e.g. from production traffic. No
+    /// client side validation or output processing is applied. Returns an error if the bytes
+    /// are not a valid `PredictRequest`.
+    pub async fn predict_raw_bytes(&mut self, bytes: &[u8]) -> Result<PredictResponse> {
+        let request = PredictRequest::decode(bytes)?;
+        self.call("predict", request, |mut c, r| async move {
+            c.prediction.predict(r).await
+        })
+        .await
+    }
+
+    /// Send a prediction and build a `PredictionResult`, using the result cache if enabled
+    async fn predict_result(
+        &mut self,
+        inputs: HashMap<String, TensorProto>,
+        model_description: ModelDescription<String>,
+    ) -> Result<PredictionResult> {
+        let key = if self.result_cache.is_enabled() {
+            let spec = self.build_model_spec(model_description.clone());
+            let key = cache::result_key(&spec, &inputs);
+            if let Some(result) = self.result_cache.get(&key) {
+                return Ok(result);
+            }
+            Some(key)
+        } else {
+            None
+        };
+
+        let resp = self.send_single(inputs, model_description.clone()).await?;
+        let result = self.prediction_result(resp, model_description).await?;
+        if let Some(key) = key {
+            self.result_cache.insert(key, &result);
+        }
+        Ok(result)
+    }
+
+    async fn prediction_result(
+        &mut self,
+        response: PredictResponse,
+        model_description: ModelDescription<String>,
+    ) -> Result<PredictionResult> {
+        let keys = match &self.output_keys {
+            Some(keys) => keys.clone(),
+            None if self.infer_output_keys => match self.signature(model_description).await {
+                Ok(signature) => OutputKeys::from_signature(&signature),
+                Err(e) => {
+                    debug!("could not infer output keys, using defaults: {}", e);
+                    OutputKeys::default()
+                }
+            },
+            None => OutputKeys::default(),
+        };
+
+        Ok(PredictionResult::from_raw_with_keys(response, &keys)?.with_labels(self.labels.clone()))
+    }
+
+    /// Send a single prediction, as part of a micro batch if enabled
+    async fn send_single(
+        &mut self,
+        inputs: HashMap<String, TensorProto>,
+        model_description: ModelDescription<String>,
+    ) -> Result<PredictResponse> {
+        if !self.micro_batcher.is_enabled() {
+            return self
+                .send_predict(inputs, model_description, Vec::new())
+                .await;
+        }
+
+        let spec = self.build_model_spec(model_description.clone());
+        let metadata = self.request(())?.metadata().clone();
+        let key = batching::batch_key(&spec, &inputs, &metadata);
+        let in_flight = self.in_flight.start().ok_or("client has been shut down")?;
+        let (joined, response) = self.micro_batcher.join(key, inputs, in_flight);
+        if let Joined::Leader(id, flushed) = joined {
+            let delay = tokio::time::delay_for(self.micro_batcher.max_delay());
+            let batch = match future::select(flushed, delay).await {
+                Either::Left((batch, _)) => batch.ok(),
+                // The batch may have filled up while the delay was finishing
+                Either::Right((_, flushed)) => match self.micro_batcher.take(key, id) {
+                    Some(batch) => Some(batch),
+                    None => flushed.await.ok(),
+                },
+            };
+            if let Some(batch) = batch {
+                self.send_batch(batch, model_description).await;
+            }
+        }
+
+        response
+            .await
+            .map_err(|_| "micro batch was dropped before it was sent")?
+    }
+
+    /// Send a micro batch as one request, and hand the outputs back to its calls
+    async fn send_batch(
+        &mut self,
+        batch: Vec<Pending>,
+        model_description: ModelDescription<String>,
+    ) {
+        debug!("sending micro batch of {} predictions", batch.len());
+        let result = match batching::merge_inputs(&batch) {
+            Ok(inputs) => {
+                self.send_predict_unsqueezed(inputs, model_description, Vec::new())
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        batching::fan_out(batch, result, self.squeeze_batch);
+    }
+
+    async fn send_predict<F, S>(
+        &mut self,
+        inputs: HashMap<String, TensorProto>,
+        model_description: S,
+        output_filter: Vec<String>,
+    ) -> Result<PredictResponse>
+    where
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        let mut resp = self
+            .send_predict_unsqueezed(inputs, model_description, output_filter)
+            .await?;
+        if self.squeeze_batch {
+            resp.outputs.values_mut().for_each(squeeze_batch);
+        }
+        Ok(resp)
+    }
+
+    async fn send_predict_unsqueezed<F, S>(
+        &mut self,
+        inputs: HashMap<String, TensorProto>,
+        model_description: S,
+        output_filter: Vec<String>,
+    ) -> Result<PredictResponse>
+    where
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        if inputs.is_empty() {
+            return Err(InputError::NoInputs.into());
+        }
+
+        let model_spec = self.build_model_spec(model_description);
+        if self.validate_inputs {
+            let metadata = self.metadata_for_spec(model_spec.clone()).await?;
+            let signature = signature::find_signature(&metadata, &model_spec.signature_name)?;
+            signature::validate_inputs(&signature, &inputs)?;
+        }
+
+        let request = predict_request(model_spec, inputs, output_filter, self.tensor_content);
+        #[cfg(feature = "tracing")]
+        log_request(&request);
+
+        let mut metadata = Vec::new();
+        if self.attach_input_checksum {
+            let checksum = format!("{:08x}", input_checksum(&request.inputs));
+            metadata.push((
+                "x-input-crc32",
+                MetadataValue::from_str(&checksum).expect("hex digits are valid metadata"),
+            ));
+        }
+
+        let resp = self
+            .call_with_metadata("predict", request, metadata, |mut c, r| async move {
+                c.prediction.predict(r).await
+            })
+            .await?;
+        self.check_min_version(&resp)?;
+        Ok(resp)
+    }
+
+    /// Run a prediction (see [predict-with-preprocessing](#method.predict_with_preprocessing))
+    ///
+    /// The pixel values are sent unscaled, unless the client was built with
+    /// [infer_input_dtype](struct.TensorflowServingBuilder.html#method.infer_input_dtype),
+    /// so no preprocessing function is needed:
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let result = serving.predict("cat.jpg", "resnet").await?;
+    /// println!("class {} with confidence {:?}", result.max_idx, result.confidence());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn predict<I, F, S>(
+        &mut self,
+        img: I,
+        model_description: S,
+    ) -> Result<PredictionResult>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let desc = model_description.into().into_owned();
+        if self.infer_input_dtype {
+            let img = img.to_image()?;
+            let max = preprocessing::sample_max(&img);
+            match self.input_dtype(desc.clone()).await {
+                Some(DataType::DtUint8) => {
+                    let tensor = preprocessing::to_tensor(
+                        img,
+                        |p| p * f32::from(u8::MAX) / max,
+                        self.image_options.layout,
+                        DataType::DtUint8,
+                        &self.image_options,
+                    )?;
+                    let mut inputs = HashMap::new();
+                    inputs.insert(self.input_name.clone(), tensor);
+                    return self.predict_result(inputs, desc).await;
+                }
+                Some(DataType::DtUint16) => {
+                    let tensor = preprocessing::to_tensor(
+                        img,
+                        |p| p * f32::from(u16::MAX) / max,
+                        self.image_options.layout,
+                        DataType::DtUint16,
+                        &self.image_options,
+                    )?;
+                    let mut inputs = HashMap::new();
+                    inputs.insert(self.input_name.clone(), tensor);
+                    return self.predict_result(inputs, desc).await;
+                }
+                Some(DataType::DtFloat) => {
+                    return self
+                        .predict_with_preprocessing(img, desc, |p| p / max)
+                        .await;
+                }
+                _ => return self.predict_with_preprocessing(img, desc, |p| p).await,
+            }
+        }
+        self.predict_with_preprocessing(img, desc, |p| p).await
+    }
+
+    /// Run a prediction for several images in one request
+    ///
+    /// The images are stacked along the batch dimension, `[N, height, width, channels]`,
+    /// and sent as one request, which gives much higher throughput than one request per
+    /// image. The pixel values are sent unscaled, as for [predict](#method.predict), and the
+    /// results are returned in the same order as `images`. Convert them into a
+    /// [`PredictionBatch`](struct.PredictionBatch.html) for statistics across the batch.
+    ///
+    /// Every image must have the same size once the client's image options are applied, so
+    /// an [`InputError::ShapeMismatch`](enum.InputError.html) is returned for images of
+    /// different sizes unless the client resizes them, e.g. with
+    /// [resize_to](struct.TensorflowServingBuilder.html#method.resize_to).
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let batch = serving
+    ///     .predict_batch(vec!["cat.jpg", "dog.jpg"], "resnet")
+    ///     .await?;
+    /// for result in &batch {
+    ///     println!("class {}", result.max_idx);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn predict_batch<I, F, S>(
+        &mut self,
+        images: Vec<I>,
+        model_description: S,
+    ) -> Result<Vec<PredictionResult>>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let desc = model_description.into().into_owned();
+        let n_images = images.len();
+        let tensors = images
+            .into_iter()
+            .map(|img| self.image_tensor(img, |p| p))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut inputs = HashMap::new();
+        inputs.insert(self.input_name.clone(), batching::stack_rows(tensors)?);
+        let resp = self
+            .send_predict_unsqueezed(inputs, desc.clone(), Vec::new())
+            .await?;
+
+        let mut results = Vec::with_capacity(n_images);
+        for mut part in batching::split_response(resp, &vec![1; n_images])? {
+            if self.squeeze_batch {
+                part.outputs.values_mut().for_each(squeeze_batch);
+            }
+            results.push(self.prediction_result(part, desc.clone()).await?);
+        }
+        Ok(results)
+    }
+
+    /// Data type of the signature's image input, if the metadata can be fetched
+    async fn input_dtype(
+        &mut self,
+        model_description: ModelDescription<String>,
+    ) -> Option<DataType> {
+        match self.signature(model_description).await {
+            Ok(signature) => signature
+                .inputs
+                .get(&self.input_name)
+                .and_then(|input| DataType::from_i32(input.dtype)),
+            Err(e) => {
+                debug!("could not infer input data type, sending floats: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Run a prediction for every frame of an animated image
+    ///
+    /// Animated GIFs and PNGs (APNG) are decoded frame by frame, and a prediction is made for
+    /// each frame, returning the results in frame order. Other image formats, including
+    /// animated WebP which the `image` crate cannot decode frame by frame, are treated as a
+    /// single frame. Frames are preprocessed like any other image, so the configured
+    /// [channels](struct.TensorflowServingBuilder.html#method.channels) decide whether their
+    /// alpha channel is kept.
+    ///
+    /// Every frame is decoded up front to a full size RGBA image, so memory use grows with the
+    /// number of frames: a 100 frame 1024x1024 animation needs around 400MB. Split long
+    /// animations before predicting on them.
+    pub async fn predict_frames<P, F, S>(
+        &mut self,
+        path: P,
+        model_description: S,
+    ) -> Result<Vec<PredictionResult>>
+    where
+        P: AsRef<Path>,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let bytes = std::fs::read(path)?;
+        let frames = match image::guess_format(&bytes)? {
+            image::ImageFormat::Gif => {
+                decode_frames(image::codecs::gif::GifDecoder::new(bytes.as_slice())?)?
+            }
+            image::ImageFormat::Png => {
+                let decoder = image::codecs::png::PngDecoder::new(bytes.as_slice())?;
+                if decoder.is_apng() {
+                    decode_frames(decoder.apng())?
+                } else {
+                    vec![image::load_from_memory(&bytes)?]
+                }
+            }
+            _ => vec![image::load_from_memory(&bytes)?],
+        };
+
+        let desc = model_description.into().into_owned();
+        let mut results = Vec::with_capacity(frames.len());
+        for frame in frames {
+            results.push(self.predict(frame, desc.clone()).await?);
+        }
+        Ok(results)
+    }
+
+    /// Run a prediction and check it against the expected class
+    ///
+    /// Returns the prediction, and whether the most likely class is `expected_idx`. Useful for
+    /// evaluation harnesses computing accuracy over a labelled dataset.
+    pub async fn predict_and_score<I, F, S>(
+        &mut self,
+        img: I,
+        model_description: S,
+        expected_idx: usize,
+    ) -> Result<(PredictionResult, bool)>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let result = self.predict(img, model_description).await?;
+        let correct = result.max_idx == expected_idx;
+        Ok((result, correct))
+    }
+
+    /// Run a prediction for a numeric input
+    ///
+    /// Sends `values` as a `DT_FLOAT` tensor named "input", or the name set with
+    /// [input_tensor_name](struct.TensorflowServingBuilder.html#method.input_tensor_name),
+    /// with the shape inferred from the nesting of the values (see
+    /// [`NestedTensor`](trait.NestedTensor.html)). This is suitable for non-image models, so
+    /// the raw response is returned.
+    pub async fn predict_values<T, F, S>(
+        &mut self,
+        values: &T,
+        model_description: S,
+    ) -> Result<PredictResponse>
+    where
+        T: NestedTensor + ?Sized,
+        F: Into<String>,
+        S: Into<ModelDescription<F>>,
+    {
+        let mut inputs = HashMap::new();
+        inputs.insert(self.input_name.clone(), values.to_tensor()?);
+
+        self.predict_inputs(inputs, model_description).await
+    }
+
+    /// Run a prediction over overlapping tiles of a large image
+    ///
+    /// The image is split into square tiles of `tile_size` pixels, with neighbouring tiles
+    /// overlapping by `overlap` pixels, and a prediction is made for each tile. Tiles at the
+    /// right and bottom edges are shifted inwards so that every tile is full size, which means
+    /// they may overlap their neighbours by more than `overlap`. Images smaller than
+    /// `tile_size` are sent as a single tile.
+    ///
+    /// Use [`stitch_tiles`](fn.stitch_tiles.html) to reassemble the per-tile outputs.
+    pub async fn predict_tiled<I, F, S>(
+        &mut self,
+        img: I,
+        tile_size: u32,
+        overlap: u32,
+        model_description: S,
+    ) -> Result<Vec<Tile>>
+    where
+        I: Image,
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        if tile_size == 0 || overlap >= tile_size {
+            return Err("tile size must be greater than zero and larger than the overlap".into());
+        }
+
+        let img = img.to_image()?;
+        let desc = model_description.into().into_owned();
+
+        let (width, height) = img.dimensions();
+        let tile_width = tile_size.min(width);
+        let tile_height = tile_size.min(height);
+
+        let mut tiles = Vec::new();
+        for y in tiling::tile_offsets(height, tile_size, overlap) {
+            for x in tiling::tile_offsets(width, tile_size, overlap) {
+                let tile = img.crop_imm(x, y, tile_width, tile_height);
+                let response = self
+                    .predict_with_output_filter(tile, desc.clone(), |p| p, Vec::new())
+                    .await?;
+
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_width,
+                    height: tile_height,
+                    response,
+                });
+            }
+        }
+
+        Ok(tiles)
+    }
+
+    /// Fetch model status
+    ///
+    /// Query the Tensorflow serving API to get the model status. The call is made to the
+    /// `ModelService`, over the same channel as predictions. Each version of the model is
+    /// reported, so traffic can be held back until a version is available:
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let status = serving.model_status("resnet").await?;
+    /// if status.model_version_status.iter().any(|v| v.is_available()) {
+    ///     let result = serving.predict("cat.jpg", "resnet").await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn model_status<S, T>(&mut self, model_name: S) -> Result<GetModelStatusResponse>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let request = GetModelStatusRequest {
+            model_spec: Some(self.build_model_spec(model_name)),
+        };
+        self.call("get_model_status", request, |mut c, r| async move {
+            c.model.get_model_status(r).await
+        })
+        .await
+    }
+
+    /// Fetch the status of the model versions selected by a version policy
+    ///
+    /// The server reports every version of a model, so the statuses are filtered by the client
+    /// according to `policy`, except for `VersionPolicy::Label` where the label is sent to the
+    /// server to resolve. The policy takes the place of any version in `model_name`.
+    pub async fn model_status_with_policy<S, T>(
+        &mut self,
+        model_name: S,
+        policy: VersionPolicy,
+    ) -> Result<GetModelStatusResponse>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let mut model_spec = self.build_model_spec(model_name);
+        model_spec.version_choice = match &policy {
+            VersionPolicy::Label(label) => Some(VersionChoice::VersionLabel(label.clone())),
+            _ => None,
+        };
+
+        let request = GetModelStatusRequest {
+            model_spec: Some(model_spec),
+        };
+        let mut resp = self
+            .call("get_model_status", request, |mut c, r| async move {
+                c.model.get_model_status(r).await
+            })
+            .await?;
+        policy.filter(&mut resp.model_version_status);
+        Ok(resp)
+    }
+
+    /// Check that the server is reachable
+    ///
+    /// Makes a model status request for an empty model name, which is cheap for the server to
+    /// answer. Any answer from the server, including the error it returns for the unknown
+    /// model, counts as success. Only transport failures, e.g. the server being down or
+    /// unreachable, are returned as errors. This makes a full round trip, so it reflects the
+    /// current state of the connection rather than the state when the client was built.
+    pub async fn ping(&mut self) -> Result<()> {
+        let request = GetModelStatusRequest {
+            model_spec: Some(ModelSpec::default()),
+        };
+        let result = self
+            .call("get_model_status", request, |mut c, r| async move {
+                c.model.get_model_status(r).await
+            })
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(TensorflowServingError::Status(status))
+                if !retry::is_transport_failure(&status) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the server is reachable, e.g. for readiness probes
+    ///
+    /// See [ping](#method.ping) for what is checked.
+    pub async fn is_ready(&mut self) -> bool {
+        self.ping().await.is_ok()
+    }
+
+    /// Watch the status of a model
+    ///
+    /// Polls the model status every `interval` and yields a snapshot of the version statuses
+    /// each time they change, starting with the current status. Failed polls are yielded as
+    /// errors, and polling continues afterwards.
+    pub fn watch_status<S, T>(
+        &self,
+        model_name: S,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Vec<ModelVersionStatus>>>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let state = WatchState {
+            client: self.clone(),
+            description: model_name.into().into_owned(),
+            last: None,
+            first: true,
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if !state.first {
+                    tokio::time::delay_for(interval).await;
+                }
+                state.first = false;
+
+                let description = state.description.clone();
+                match state.client.model_status(description).await {
+                    Ok(resp) => {
+                        let versions = resp.model_version_status;
+                        if state.last.as_ref() != Some(&versions) {
+                            state.last = Some(versions.clone());
+                            return Some((Ok(versions), state));
+                        }
+                    }
+                    Err(e) => return Some((Err(e), state)),
+                }
+            }
+        })
+    }
+
+    /// Fetch model metadata
+    ///
+    /// The `signature_def` metadata field is requested, which describes the input and output
+    /// tensors of each signature of the model, e.g. to check their names before sending a
+    /// prediction. [signature](#method.signature) picks out the configured signature.
+    ///
+    /// If a metadata cache TTL has been configured, cached metadata may be returned.
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::TensorflowServing;
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let metadata = serving.model_metadata("resnet").await?;
+    /// println!("metadata for {:?}: {:?}", metadata.model_spec, metadata.metadata.keys());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn model_metadata<S, T>(&mut self, model_name: S) -> Result<GetModelMetadataResponse>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let model_spec = self.build_model_spec(model_name);
+        self.metadata_for_spec(model_spec).await
+    }
+
+    /// Fetch the signature definition used by this client
+    ///
+    /// Returns the definition of the configured signature (`serving_default` unless
+    /// overridden by the client or model description) from the model metadata, which describes
+    /// the expected input and output tensors. Returns an error listing the available signatures
+    /// if the configured signature is not present.
+    pub async fn signature<S, T>(&mut self, model_name: S) -> Result<SignatureDef>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let model_spec = self.build_model_spec(model_name);
+        let signature_name = model_spec.signature_name.clone();
+        let metadata = self.metadata_for_spec(model_spec).await?;
+        signature::find_signature(&metadata, &signature_name)
+    }
+
+    /// Names of the inputs the configured signature expects, in alphabetical order
+    ///
+    /// A cheap check before building a multi-input request with
+    /// [predict_inputs](#method.predict_inputs). The names come from the model metadata, and
+    /// are cached for the model and signature until
+    /// [invalidate_metadata](#method.invalidate_metadata) is called, whether or not a metadata
+    /// cache TTL has been configured.
+    pub async fn input_names<S, T>(&mut self, model_name: S) -> Result<Vec<String>>
+    where
+        S: Into<ModelDescription<T>>,
+        T: Into<String>,
+    {
+        let model_spec = self.build_model_spec(model_name);
+        if let Some(names) = self.metadata_cache.input_names(&model_spec) {
+            return Ok(names);
+        }
+
+        let metadata = self.metadata_for_spec(model_spec.clone()).await?;
+        let signature = signature::find_signature(&metadata, &model_spec.signature_name)?;
+        let mut names: Vec<String> = signature.inputs.into_keys().collect();
+        names.sort();
+        self.metadata_cache.insert_input_names(&model_spec, &names);
+        Ok(names)
+    }
+
+    async fn metadata_for_spec(
+        &mut self,
+        model_spec: ModelSpec,
+    ) -> Result<GetModelMetadataResponse> {
+        if let Some(metadata) = self.metadata_cache.get(&model_spec) {
+            return Ok(metadata);
+        }
+
+        let request = GetModelMetadataRequest {
+            model_spec: Some(model_spec.clone()),
+            metadata_field: vec!["signature_def".to_string()],
+        };
+
+        let resp = self
+            .call("get_model_metadata", request, |mut c, r| async move {
+                c.prediction.get_model_metadata(r).await
+            })
+            .await?;
+        self.metadata_cache.insert(&model_spec, &resp);
+        Ok(resp)
+    }
+
+    /// Remove all cached metadata for a model
+    ///
+    /// Call this when a new version of a model is pushed, so that the next lookup fetches its
+    /// metadata from the server (see
+    /// [metadata_cache_ttl](struct.TensorflowServingBuilder.html#method.metadata_cache_ttl)).
+    pub fn invalidate_metadata(&self, model_name: &str) {
+        self.metadata_cache.invalidate(model_name);
+    }
+
+    /// Reload model config
+    ///
+    /// Replaces the server's model config with the given list of models (see
+    /// [reload_config](#method.reload_config)).
+    pub async fn reload<C>(&mut self, model_config: C) -> Result<ReloadConfigResponse>
+    where
+        C: Into<Vec<ModelConfig>>,
+    {
+        self.reload_config(model_config.into()).await
+    }
+
+    /// Push a new model server config at runtime
+    ///
+    /// Accepts a full `ModelServerConfig`, or anything convertible into one such as a
+    /// `ModelConfigList` or a `Vec<ModelConfig>`. The server applies the config
+    /// asynchronously, and reports whether it was accepted in the `status` of the response,
+    /// so check it to detect invalid configs.
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::{ModelConfig, TensorflowServing};
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let config = vec![ModelConfig {
+    ///     name: "resnet".to_string(),
+    ///     base_path: "/models/resnet".to_string(),
+    ///     model_platform: "tensorflow".to_string(),
+    ///     ..Default::default()
+    /// }];
+    /// let resp = serving.reload_config(config).await?;
+    /// if let Some(status) = resp.status.filter(|s| s.error_code != 0) {
+    ///     eprintln!("config rejected: {}", status.error_message);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn reload_config<C>(&mut self, model_server_config: C) -> Result<ReloadConfigResponse>
+    where
+        C: Into<ModelServerConfig>,
+    {
+        let request = ReloadConfigRequest {
+            config: Some(model_server_config.into()),
+        };
+
+        self.call(
+            "handle_reload_config_request",
+            request,
+            |mut c, r| async move { c.model.handle_reload_config_request(r).await },
+        )
+        .await
+    }
+
+    /// Reload model config and wait for the configured models to load
+    ///
+    /// Sends the config with [`reload`](#method.reload), and if the server accepts it, polls the
+    /// status of every configured model each `poll_interval` until all of them are AVAILABLE,
+    /// have failed to load, or `timeout` has elapsed. `progress` is called with the model name
+    /// and its version statuses after every poll so callers can report loading progress.
+    ///
+    /// If the server rejects the config, the returned summary has `accepted` set to `false` and
+    /// no models are polled.
+    pub async fn reload_and_wait<C, P>(
+        &mut self,
+        model_config: C,
+        poll_interval: Duration,
+        timeout: Duration,
+        mut progress: P,
+    ) -> Result<ReloadSummary>
+    where
+        C: Into<Vec<ModelConfig>>,
+        P: FnMut(&str, &[ModelVersionStatus]),
+    {
+        let model_config = model_config.into();
+        let mut pending: Vec<String> = model_config.iter().map(|c| c.name.clone()).collect();
+
+        let resp = self.reload(model_config).await?;
+        let mut summary = ReloadSummary::default();
+        if let Some(status) = resp.status {
+            if status.error_code != 0 {
+                summary.message = status.error_message;
+                return Ok(summary);
+            }
+        }
+        summary.accepted = true;
+
+        let start = Instant::now();
+        loop {
+            let mut still_pending = Vec::new();
+            for name in pending {
+                // The model may not be known to the server until it starts loading
+                let versions = match self.model_status(name.as_str()).await {
+                    Ok(resp) => resp.model_version_status,
+                    Err(_) => {
+                        still_pending.push(name);
+                        continue;
+                    }
+                };
+                progress(&name, &versions);
+
+                match load_state(&versions) {
+                    LoadState::Loaded => summary.loaded.push(name),
+                    LoadState::Failed(msg) => summary.failed.push((name, msg)),
+                    LoadState::Pending => still_pending.push(name),
+                }
+            }
+            pending = still_pending;
+
+            if pending.is_empty() || start.elapsed() >= timeout {
+                break;
+            }
+            tokio::time::delay_for(poll_interval).await;
+        }
+
+        summary.pending = pending;
+        Ok(summary)
+    }
+
+    /// Run several classification and regression tasks on one input
+    ///
+    /// Every task must use the same model, as required by Tensorflow Serving. The results are
+    /// returned in the same order as `tasks`, and an error is returned if the server responds
+    /// with a result of the wrong kind for a task, e.g. a regression result for a
+    /// [`Task::classify`](enum.Task.html#method.classify) task.
+    ///
+    /// Tasks can be given as [`Task`](enum.Task.html)s, or as `(TaskType, model)` pairs:
+    ///
+    /// ```rust,no_run
+    /// # use tensorflow_serving::{Payload, TaskType, TensorflowServing};
+    /// # async fn run(serving: &mut TensorflowServing) -> tensorflow_serving::Result<()> {
+    /// let tasks = vec![(TaskType::Classify, "census"), (TaskType::Regress, "census")];
+    /// let features = vec![("age", Payload::Ints(vec![42]))];
+    /// let resp = serving.multi_inference(tasks, features).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn multi_inference<K, P, T, V>(
+        &mut self,
+        tasks: Vec<K>,
+        payload_map: P,
+    ) -> Result<MultiInferenceResponse>
+    where
+        K: Into<Task>,
+        P: IntoIterator<Item = (T, V)>,
+        T: Into<String>,
+        V: Into<Payload>,
+    {
+        let tasks: Vec<Task> = tasks.into_iter().map(Into::into).collect();
+        let model_name = match tasks.first() {
+            Some(task) => task.model_description().name.clone(),
+            None => return Err("multi inference requires at least one task".into()),
+        };
+        if let Some(task) = tasks
+            .iter()
+            .find(|t| t.model_description().name != model_name)
+        {
+            return Err(format!(
+                "all multi inference tasks must use the same model, found {} and {}",
+                model_name,
+                task.model_description().name
+            )
+            .into());
+        }
+
+        let request = MultiInferenceRequest {
+            tasks: tasks
+                .iter()
+                .map(|task| InferenceTask {
+                    model_spec: Some(self.build_model_spec(task.model_description().clone())),
+                    method_name: task.method_name().to_string(),
+                })
+                .collect(),
+            input: Some(self.build_input(payload_map)?),
+        };
+
+        let resp = self
+            .call("multi_inference", request, |mut c, r| async move {
+                c.prediction.multi_inference(r).await
+            })
+            .await?;
+
+        if resp.results.len() != tasks.len() {
+            return Err(format!(
+                "expected {} inference results, one per task, got {}",
+                tasks.len(),
+                resp.results.len()
+            )
+            .into());
+        }
+        for (i, (task, result)) in tasks.iter().zip(&resp.results).enumerate() {
+            if !task.matches(result) {
+                return Err(TensorflowServingError::TaskMismatch {
+                    task: i,
+                    method_name: task.method_name().to_string(),
+                });
+            }
+        }
+
+        Ok(resp)
+    }
+
+    /// Run a regression job
+    ///
+    /// The features are given as `(name, payload)` pairs, as for [classify](#method.classify).
+    /// The result holds one regression value per example. Returns an error if the response
+    /// does not contain a result.
+    pub async fn regress<S, P, T, F, V>(
+        &mut self,
+        model_name: S,
+        payload_map: P,
+    ) -> Result<RegressionResult>
+    where
         S: Into<ModelDescription<F>>,
-        M: Fn(f32) -> f32,
+        F: Into<String>,
+        P: IntoIterator<Item = (T, V)>,
+        T: Into<String>,
+        V: Into<Payload>,
     {
-        // Load data
-        let img = img.to_image()?;
+        let req = RegressionRequest {
+            model_spec: Some(self.build_model_spec(model_name)),
+            input: Some(self.build_input(payload_map)?),
+        };
 
-        let (width, height) = img.dimensions();
-        let dims: Vec<_> = [1, width as i64, height as i64, 3]
-            .iter()
-            .map(|d| tensor_shape_proto::Dim {
-                size: *d,
-                name: "".to_string(),
+        let resp = self
+            .call("regress", req, |mut c, r| async move {
+                c.prediction.regress(r).await
             })
-            .collect();
-
-        let pixels: Vec<_> = img
-            .raw_pixels()
-            .iter()
-            .map(|p| *p as f32)
-            .map(preprocessing_fn)
-            .collect();
+            .await?;
 
-        let tensor_shape = TensorShapeProto {
-            dim: dims,
-            ..Default::default()
-        };
+        Ok(resp
+            .result
+            .ok_or("regression result missing from response")?)
+    }
 
-        let tensor = TensorProto {
-            dtype: 1,
-            tensor_shape: Some(tensor_shape),
-            float_val: pixels,
-            ..Default::default()
-        };
+    /// Use a request ID for every call made by the returned client
+    ///
+    /// Returns a copy of the client which sends `request_id` as the `x-request-id` metadata on
+    /// each call, overriding the default set with the builder. As clients are cheap to clone
+    /// this can be used per call, e.g. `serving.with_request_id(id).predict(img, model)`.
+    pub fn with_request_id<S: Into<String>>(&self, request_id: S) -> TensorflowServing {
+        let mut client = self.clone();
+        client.request_id = Some(request_id.into());
+        client
+    }
 
-        let mut inputs = HashMap::new();
-        inputs.insert("input".into(), tensor);
+    /// Stop accepting new calls and wait for in-flight calls to finish
+    ///
+    /// `Drop` cannot wait for requests, so call this during graceful shutdown. Clones of a
+    /// client share their in-flight calls, so this waits for calls made through any clone, and
+    /// calls made through a clone after shutdown has started return an error.
+    pub async fn shutdown(self) {
+        self.in_flight.shutdown().await
+    }
 
-        let request = PredictRequest {
-            model_spec: Some(self.build_model_spec(model_description)),
-            inputs,
-            ..Default::default()
-        };
+    /// Check a model can be reached and accepts requests matching its signature
+    ///
+    /// Fetches the signature, and sends one prediction with an all-zero tensor for every
+    /// input, e.g. a single black image sized from the signature, with unknown dimensions set
+    /// to 1. Returns an error if the request fails, or if the response is missing any of the
+    /// outputs listed in the signature. This is intended as a one line check in deployment
+    /// pipelines, so it bypasses the result cache and micro batching.
+    pub async fn smoke_test<F, S>(&mut self, model_description: S) -> Result<()>
+    where
+        S: Into<ModelDescription<F>>,
+        F: Into<String>,
+    {
+        let desc = model_description.into().into_owned();
+        let signature = self.signature(desc.clone()).await?;
+        let inputs = signature
+            .inputs
+            .iter()
+            .map(|(name, info)| Ok((name.clone(), tensor::zero_tensor(info)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
 
-        let resp = self.prediction_client.predict(request).await?;
-        Ok(resp.into_inner())
+        let response = self.send_predict(inputs, desc, Vec::new()).await?;
+        if response.outputs.is_empty() {
+            return Err("smoke test response has no outputs".into());
+        }
+        let mut missing: Vec<_> = signature
+            .outputs
+            .keys()
+            .filter(|name| !response.outputs.contains_key(*name))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(format!(
+                "smoke test response is missing outputs: {}",
+                missing.join(", ")
+            )
+            .into());
+        }
+        Ok(())
     }
 
-    /// Run a prediction (see [predict-with-preprocessing](struct.TensorflowServing.html#method.predict_with_preprocessing))
-    pub async fn predict<I, F, S>(
+    /// Measure prediction latency and throughput against a model
+    ///
+    /// Sends predictions for `img` from `concurrency` concurrent callers sharing the client's
+    /// channel, for `duration`, and reports the latency percentiles and predictions per
+    /// second. The image is preprocessed once up front, and the result cache is bypassed, so
+    /// the report reflects the round trip to the server. Failed predictions are counted but
+    /// do not stop the benchmark.
+    pub async fn benchmark<I, F, S>(
         &mut self,
         img: I,
         model_description: S,
-    ) -> Result<PredictResponse>
+        concurrency: usize,
+        duration: Duration,
+    ) -> Result<BenchmarkReport>
     where
         I: Image,
         S: Into<ModelDescription<F>>,
         F: Into<String>,
     {
-        self.predict_with_preprocessing(img, model_description, |p| p)
-            .await
+        let mut inputs = HashMap::new();
+        inputs.insert(self.input_name.clone(), self.image_tensor(img, |p| p)?);
+        let desc = model_description.into().into_owned();
+
+        let start = Instant::now();
+        let deadline = start + duration;
+        let workers = (0..concurrency.max(1)).map(|_| {
+            let mut client = self.clone();
+            let inputs = inputs.clone();
+            let desc = desc.clone();
+            async move {
+                let mut latencies = Vec::new();
+                let mut errors = 0;
+                while Instant::now() < deadline {
+                    let sent = Instant::now();
+                    match client
+                        .send_predict(inputs.clone(), desc.clone(), Vec::new())
+                        .await
+                    {
+                        Ok(_) => latencies.push(sent.elapsed()),
+                        Err(e) => {
+                            debug!("benchmark prediction failed: {}", e);
+                            errors += 1;
+                        }
+                    }
+                }
+                (latencies, errors)
+            }
+        });
+
+        let mut latencies = Vec::new();
+        let mut errors = 0;
+        for (worker_latencies, worker_errors) in future::join_all(workers).await {
+            latencies.extend(worker_latencies);
+            errors += worker_errors;
+        }
+        Ok(BenchmarkReport::from_latencies(
+            latencies,
+            errors,
+            start.elapsed(),
+        ))
     }
 
-    /// Fetch model status
+    /// Wait for in-flight calls to finish, then close the connection to the server
     ///
-    /// Query the Tensorflow serving API to get the model status
-    pub async fn model_status<S, T>(&mut self, model_name: S) -> Result<GetModelStatusResponse>
-    where
-        S: Into<ModelDescription<T>>,
-        T: Into<String>,
-    {
-        let request = GetModelStatusRequest {
-            model_spec: Some(self.build_model_spec(model_name)),
-        };
-        let resp = self.model_client.get_model_status(request).await?;
-        Ok(resp.into_inner())
+    /// Behaves like [shutdown](#method.shutdown), then drops the channel, so the connection is
+    /// closed as soon as no clone of the client is using it.
+    ///
+    /// Predictions still waiting in a
+    /// [micro batch](struct.TensorflowServingBuilder.html#method.micro_batch) are not in
+    /// flight yet, so they are not waited for: once the batch is due to be sent it fails with
+    /// an error, which is returned to every prediction in the batch.
+    pub async fn close(self) {
+        let TensorflowServing {
+            clients, in_flight, ..
+        } = self;
+        in_flight.shutdown().await;
+        drop(clients);
     }
 
-    /// Fetch model metadata
-    pub async fn model_metadata<S, T>(&mut self, model_name: S) -> Result<GetModelMetadataResponse>
+    // Private helper functions
+
+    /// Send a request, reconnecting and retrying after transport failures
+    ///
+    /// `call` is invoked with the service clients and the request for every attempt, and the
+    /// stats of the successful attempt are recorded.
+    async fn call<Req, Resp, C, Fut>(
+        &mut self,
+        method: &'static str,
+        message: Req,
+        call: C,
+    ) -> Result<Resp>
     where
-        S: Into<ModelDescription<T>>,
-        T: Into<String>,
+        Req: Message + Clone,
+        Resp: Message,
+        C: Fn(Clients, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
     {
-        let request = GetModelMetadataRequest {
-            model_spec: Some(self.build_model_spec(model_name)),
-            metadata_field: vec!["signature_def".to_string()],
-        };
-
-        let resp = self.prediction_client.get_model_metadata(request).await?;
-        Ok(resp.into_inner())
+        self.call_with_metadata(method, message, Vec::new(), call)
+            .await
     }
 
-    /// Reload model config
-    pub async fn reload<C>(&mut self, model_config: C) -> Result<ReloadConfigResponse>
+    /// Send a request like [call](#method.call), adding `metadata` to every attempt
+    async fn call_with_metadata<Req, Resp, C, Fut>(
+        &mut self,
+        method: &'static str,
+        message: Req,
+        metadata: Vec<(&'static str, MetadataValue<Ascii>)>,
+        call: C,
+    ) -> Result<Resp>
     where
-        C: Into<Vec<ModelConfig>>,
+        Req: Message + Clone,
+        Resp: Message,
+        C: Fn(Clients, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
     {
-        let config = model_server_config::Config::ModelConfigList(ModelConfigList {
-            config: model_config.into(),
-        });
+        let _guard = self.in_flight.start().ok_or("client has been shut down")?;
+        let request_bytes = message.encoded_len();
+        if let Some(delay) = self.backpressure.delay(&self.retry_policy) {
+            debug!("server overloaded, delaying {} by {:?}", method, delay);
+            tokio::time::delay_for(delay).await;
+        }
 
-        let request = ReloadConfigRequest {
-            config: Some(ModelServerConfig {
-                config: Some(config),
-            }),
-        };
+        let mut attempt = 0;
+        loop {
+            let error: TensorflowServingError = match self.try_reconnect().await {
+                Ok(()) => {
+                    let mut request = self.request(message.clone())?;
+                    for (key, value) in &metadata {
+                        request.metadata_mut().insert(*key, value.clone());
+                    }
+                    let start = Instant::now();
+                    match call(self.clients.clone(), request).await {
+                        Ok(resp) => {
+                            let resp = resp.into_inner();
+                            self.backpressure.record_success();
+                            self.record(method, request_bytes, &resp, start);
+                            return Ok(resp);
+                        }
+                        Err(status) if retry::is_transport_failure(&status) => {
+                            self.reconnect = true;
+                            status.into()
+                        }
+                        Err(status) if retry::is_resource_exhausted(&status) => {
+                            self.backpressure.record_exhausted();
+                            status.into()
+                        }
+                        Err(status) => return Err(status.into()),
+                    }
+                }
+                Err(e) => e.into(),
+            };
 
-        let resp = self
-            .model_client
-            .handle_reload_config_request(request)
-            .await?;
-        Ok(resp.into_inner())
+            if attempt >= self.retry_policy.max_retries {
+                return Err(error);
+            }
+            let backoff = self.retry_policy.backoff(attempt);
+            debug!(
+                "{} failed: {}, retrying in {:?} ({} of {})",
+                method,
+                error,
+                backoff,
+                attempt + 1,
+                self.retry_policy.max_retries
+            );
+            tokio::time::delay_for(backoff).await;
+            attempt += 1;
+        }
     }
 
-    /*
-    /// Run a regression job
-    pub fn regress<S, T, F, V>(
-        &self,
-        model_name: S,
-        payload_map: HashMap<T, V>,
-    ) -> Result<regression::RegressionResult>
-    where
-        S: Into<ModelDescription<F>>,
-        F: Into<String>,
-        T: Into<String>,
-        V: Into<Payload>,
-    {
-        let req = regression::RegressionRequest {
-            model_spec: Some(self.build_model_spec(model_name)).into(),
-            input: Some(self.build_input(payload_map)).into(),
-            ..Default::default()
-        };
+    /// Re-establish the channel if the last call found it broken
+    ///
+    /// Clients wrapping a channel supplied with [from_channel](#method.from_channel) have no
+    /// endpoint to reconnect to, so they rely on the channel to recover.
+    async fn try_reconnect(&mut self) -> std::result::Result<(), tonic::transport::Error> {
+        if self.reconnect {
+            if !self.endpoints.is_empty() {
+                debug!("reconnecting to {:?}", self.endpoints);
+                let prediction_service = self.clients.prediction.service();
+                self.clients = Clients::connect(&self.endpoints, prediction_service).await?;
+            }
+            self.reconnect = false;
+        }
+        Ok(())
+    }
 
-        let resp = self.prediction_client.regress(&req)?;
-        Ok(resp.result.unwrap())
+    fn check_min_version(&self, response: &PredictResponse) -> Result<()> {
+        let served = response
+            .model_spec
+            .as_ref()
+            .and_then(|spec| match spec.version_choice {
+                Some(VersionChoice::Version(version)) => Some(version),
+                _ => None,
+            });
+        match (self.min_version, served) {
+            (Some(min_version), Some(version)) if version < min_version => {
+                Err(TensorflowServingError::VersionTooOld {
+                    version,
+                    min_version,
+                })
+            }
+            _ => Ok(()),
+        }
     }
 
+    fn request<T>(&self, message: T) -> Result<tonic::Request<T>> {
+        let mut request = tonic::Request::new(message);
+        if let Some(request_id) = &self.request_id {
+            request.metadata_mut().insert(
+                "x-request-id",
+                MetadataValue::from_str(request_id)
+                    .map_err(|e| format!("invalid request ID {}: {}", request_id, e))?,
+            );
+        }
+        if let Some(metadata) = &self.call_metadata {
+            // Call level values replace connection level values for the same key
+            let mut headers = std::mem::take(request.metadata_mut()).into_headers();
+            let call_headers = metadata.clone().into_headers();
+            for key in call_headers.keys() {
+                headers.remove(key);
+            }
+            for (key, value) in call_headers.iter() {
+                headers.append(key, value.clone());
+            }
+            *request.metadata_mut() = MetadataMap::from_headers(headers);
+        }
+        Ok(request)
+    }
 
-    /// Perform multi-inference
-    pub fn multi_inference<S, V>(
+    fn record<R: Message>(
         &self,
-        _model_name: S,
-        _payload_map: HashMap<S, V>,
-    ) -> Result<inference::MultiInferenceResponse>
-    where
-        S: Into<String>,
-        V: Into<Payload>,
-    {
-        unimplemented!();
-        /*
-        let request = inference::MultiInferenceRequest {
-            // TODO: tasks
-            input: Some(self.build_input(payload_map)).into(),
-            ..Default::default()
+        method: &'static str,
+        request_bytes: usize,
+        response: &R,
+        start: Instant,
+    ) {
+        let stats = RequestStats {
+            method,
+            request_id: self.request_id.clone(),
+            request_bytes,
+            response_bytes: response.encoded_len(),
+            elapsed: start.elapsed(),
         };
-
-        self.prediction_client
-            .multi_inference(&request)
-            .context("sending request to server")
-            .map_err(From::from)
-        */
+        debug!(
+            "{} request{}: {} bytes, response: {} bytes, took {:?}",
+            stats.method,
+            stats
+                .request_id
+                .as_ref()
+                .map(|id| format!(" {}", id))
+                .unwrap_or_default(),
+            stats.request_bytes,
+            stats.response_bytes,
+            stats.elapsed
+        );
+
+        if let Some(observer) = &self.observer {
+            observer(&stats);
+        }
     }
 
-    /// Reload the model configs
-    pub fn reload_config<H>(&self, model_map: H) -> Result<model_management::ReloadConfigResponse>
+    fn image_tensor<I, M>(&self, img: I, preprocessing_fn: M) -> Result<TensorProto>
     where
-        H: Into<model_server_config::ModelConfigList>,
+        I: Image,
+        M: Fn(f32) -> f32,
     {
-        // request -> modelserverconfig -> ModelConfigList
-        //
-        let config_list = model_map.into();
-        let model_server_config = model_server_config::ModelServerConfig {
-            config: Some(
-                model_server_config::ModelServerConfig_oneof_config::model_config_list(config_list),
-            ),
-            ..Default::default()
-        };
-
-        let request = model_management::ReloadConfigRequest {
-            config: Some(model_server_config).into(),
-            ..Default::default()
-        };
-
-        self.model_management_client
-            .handle_reload_config_request(&request)
-            .context("sending request to server")
-            .map_err(From::from)
+        preprocessing::to_tensor(
+            img,
+            preprocessing_fn,
+            self.image_options.layout,
+            DataType::DtFloat,
+            &self.image_options,
+        )
     }
-    */
 
-    // Private helper functions
-    fn build_input<S, V>(&self, payload_map: HashMap<S, V>) -> Input
+    fn build_input<P, S, V>(&self, payload_map: P) -> Result<Input>
     where
+        P: IntoIterator<Item = (S, V)>,
         S: Into<String>,
         V: Into<Payload>,
     {
-        // Build Feature
-        let ft = payload_map.to_features();
-
         // Build Vec<Example>
-        let example = Example { features: Some(ft) };
+        let example = self.build_example(payload_map)?;
         // Build ExampleList
         let example_list = ExampleList {
             examples: vec![example],
         };
         // Build Input
-        Input {
+        Ok(Input {
             kind: Some(input::Kind::ExampleList(example_list)),
-        }
+        })
+    }
+
+    fn build_example<P, S, V>(&self, payload_map: P) -> Result<Example>
+    where
+        P: IntoIterator<Item = (S, V)>,
+        S: Into<String>,
+        V: Into<Payload>,
+    {
+        Ok(Example {
+            features: Some(payload_map.to_features(self.allow_duplicates)?),
+        })
     }
 
     fn build_model_spec<S, T>(&self, model_description: S) -> ModelSpec
@@ -442,11 +2876,109 @@ impl TensorflowServing {
         ModelSpec {
             name: desc.name.into(),
             version_choice: version,
-            signature_name: self.signature_name.clone(),
+            signature_name: desc
+                .signature_name
+                .unwrap_or_else(|| self.signature_name.clone()),
+        }
+    }
+}
+
+/// Service clients sharing one channel to the server
+#[derive(Clone)]
+struct Clients {
+    prediction: PredictionService,
+    model: ModelServiceClient<Channel>,
+}
+
+impl Clients {
+    fn new(channel: Channel, prediction_service: Arc<str>) -> Self {
+        Clients {
+            prediction: PredictionService::new(channel.clone(), prediction_service),
+            model: ModelServiceClient::new(channel),
+        }
+    }
+
+    /// Connect to `endpoints`, balancing calls between them when there is more than one
+    async fn connect(
+        endpoints: &[Endpoint],
+        prediction_service: Arc<str>,
+    ) -> std::result::Result<Self, tonic::transport::Error> {
+        let channel = match endpoints {
+            [endpoint] => endpoint.connect().await?,
+            _ => Channel::balance_list(endpoints.iter().cloned()),
+        };
+        Ok(Clients::new(channel, prediction_service))
+    }
+}
+
+/// Outcome of a [`reload_and_wait`](struct.TensorflowServing.html#method.reload_and_wait) call
+#[derive(Debug, Default)]
+pub struct ReloadSummary {
+    /// Whether the server accepted the new config
+    pub accepted: bool,
+    /// Error message returned by the server if the config was rejected
+    pub message: String,
+    /// Models that finished loading and are AVAILABLE
+    pub loaded: Vec<String>,
+    /// Models that failed to load, along with the reported error message
+    pub failed: Vec<(String, String)>,
+    /// Models which were still loading when the timeout elapsed
+    pub pending: Vec<String>,
+}
+
+struct WatchState {
+    client: TensorflowServing,
+    description: ModelDescription<String>,
+    last: Option<Vec<ModelVersionStatus>>,
+    first: bool,
+}
+
+enum LoadState {
+    Loaded,
+    Failed(String),
+    Pending,
+}
+
+fn load_state(versions: &[ModelVersionStatus]) -> LoadState {
+    if let Some(failure) = versions
+        .iter()
+        .filter_map(|v| v.status.as_ref())
+        .find(|s| s.error_code != 0)
+    {
+        return LoadState::Failed(failure.error_message.clone());
+    }
+
+    // Old versions being unloaded end up in the END state, so only consider the others
+    let mut live = versions.iter().filter(|v| v.state != State::End as i32);
+    let mut any = false;
+    let all_available = live.all(|v| {
+        any = true;
+        v.is_available()
+    });
+
+    if any && all_available {
+        LoadState::Loaded
+    } else {
+        LoadState::Pending
+    }
+}
+
+impl From<ModelConfigList> for ModelServerConfig {
+    fn from(model_config_list: ModelConfigList) -> Self {
+        ModelServerConfig {
+            config: Some(model_server_config::Config::ModelConfigList(
+                model_config_list,
+            )),
         }
     }
 }
 
+impl From<Vec<ModelConfig>> for ModelServerConfig {
+    fn from(config: Vec<ModelConfig>) -> Self {
+        ModelConfigList { config }.into()
+    }
+}
+
 /// Description of a model
 ///
 /// This struct is used to specify a model, and optionally a version of a model. It
@@ -458,7 +2990,7 @@ impl TensorflowServing {
 /// let model_name = "resnet";
 /// let description: ModelDescription<_> = model_name.into();
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ModelDescription<S>
 where
     S: Into<String>,
@@ -467,6 +2999,21 @@ where
     pub name: S,
     /// Optional version of the model
     pub version: Option<i64>,
+    /// Optional signature name, overriding the signature name of the client
+    pub signature_name: Option<String>,
+}
+
+impl<S> ModelDescription<S>
+where
+    S: Into<String>,
+{
+    fn into_owned(self) -> ModelDescription<String> {
+        ModelDescription {
+            name: self.name.into(),
+            version: self.version,
+            signature_name: self.signature_name,
+        }
+    }
 }
 
 impl<S> From<S> for ModelDescription<S>
@@ -477,13 +3024,14 @@ where
         ModelDescription {
             name: s,
             version: None,
+            signature_name: None,
         }
     }
 }
 
 /// Clssification payload
 ///
-/// Can be either bytes, ints, or floats
+/// Can be either bytes, ints, floats, 16-bit ints, or complex numbers
 pub enum Payload {
     /// Bytes payload
     Bytes(Vec<Vec<u8>>),
@@ -491,6 +3039,70 @@ pub enum Payload {
     Ints(Vec<i64>),
     /// Floats payload
     Floats(Vec<f32>),
+    /// Unsigned 16-bit ints payload, sent as a `DT_UINT16` tensor
+    UInt16(Vec<u16>),
+    /// Signed 16-bit ints payload, sent as a `DT_INT16` tensor
+    Int16(Vec<i16>),
+    /// Complex payload of (real, imaginary) pairs
+    ///
+    /// Sent as a `DT_COMPLEX64` tensor. Examples have no complex feature type, so as a feature
+    /// the real and imaginary parts are interleaved into a float list.
+    Complex64(Vec<(f32, f32)>),
+}
+
+impl Payload {
+    /// Tensorflow data type of the payload values
+    ///
+    /// Tensors carry the data type as its protobuf enum value, which must keep Tensorflow's
+    /// numbering, so a regenerated proto which renumbered the enum would break requests:
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{DataType, Payload};
+    /// assert_eq!(DataType::DtFloat as i32, 1);
+    /// let tensor = Payload::Floats(vec![0.5]).into_tensor(&[1]);
+    /// assert_eq!(tensor.dtype, 1);
+    /// ```
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Payload::Bytes(_) => DataType::DtString,
+            Payload::Ints(_) => DataType::DtInt64,
+            Payload::Floats(_) => DataType::DtFloat,
+            Payload::UInt16(_) => DataType::DtUint16,
+            Payload::Int16(_) => DataType::DtInt16,
+            Payload::Complex64(_) => DataType::DtComplex64,
+        }
+    }
+
+    /// Build a tensor with the given shape from the payload
+    ///
+    /// The payload values are the flattened tensor values in row-major order.
+    pub fn into_tensor(self, dims: &[i64]) -> TensorProto {
+        let mut tensor = TensorProto {
+            dtype: self.data_type() as i32,
+            tensor_shape: Some(tensor::tensor_shape(dims)),
+            ..Default::default()
+        };
+
+        match self {
+            Payload::Bytes(v) => tensor.string_val = v,
+            Payload::Ints(v) => tensor.int64_val = v,
+            Payload::Floats(v) => tensor.float_val = v,
+            Payload::UInt16(v) => tensor.int_val = v.into_iter().map(i32::from).collect(),
+            Payload::Int16(v) => tensor.int_val = v.into_iter().map(i32::from).collect(),
+            Payload::Complex64(v) => tensor.scomplex_val = tensor::interleave(&v),
+        }
+
+        tensor
+    }
+
+    /// Read the contents of a file into a bytes payload
+    ///
+    /// The file is not decoded, so this is suitable for models which take encoded blobs (e.g.
+    /// JPEG data) as a feature. The payload contains a single element holding the file bytes.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Payload> {
+        let bytes = std::fs::read(path)?;
+        Ok(Payload::Bytes(vec![bytes]))
+    }
 }
 
 impl From<Vec<Vec<u8>>> for Payload {
@@ -511,6 +3123,40 @@ impl From<Vec<f32>> for Payload {
     }
 }
 
+/// Requires the `ndarray` feature
+#[cfg(feature = "ndarray")]
+impl From<ndarray::Array1<f32>> for Payload {
+    fn from(v: ndarray::Array1<f32>) -> Self {
+        Payload::Floats(v.to_vec())
+    }
+}
+
+/// Requires the `ndarray` feature
+#[cfg(feature = "ndarray")]
+impl From<ndarray::Array1<i64>> for Payload {
+    fn from(v: ndarray::Array1<i64>) -> Self {
+        Payload::Ints(v.to_vec())
+    }
+}
+
+impl From<Vec<u16>> for Payload {
+    fn from(v: Vec<u16>) -> Self {
+        Payload::UInt16(v)
+    }
+}
+
+impl From<Vec<i16>> for Payload {
+    fn from(v: Vec<i16>) -> Self {
+        Payload::Int16(v)
+    }
+}
+
+impl From<Vec<(f32, f32)>> for Payload {
+    fn from(v: Vec<(f32, f32)>) -> Self {
+        Payload::Complex64(v)
+    }
+}
+
 impl From<Payload> for Feature {
     fn from(c: Payload) -> Self {
         let data_list = match c {
@@ -527,6 +3173,27 @@ impl From<Payload> for Feature {
             Payload::Floats(v) => {
                 let data_list = FloatList { value: v };
 
+                Kind::FloatList(data_list)
+            }
+            Payload::UInt16(v) => {
+                let data_list = Int64List {
+                    value: v.into_iter().map(i64::from).collect(),
+                };
+
+                Kind::Int64List(data_list)
+            }
+            Payload::Int16(v) => {
+                let data_list = Int64List {
+                    value: v.into_iter().map(i64::from).collect(),
+                };
+
+                Kind::Int64List(data_list)
+            }
+            Payload::Complex64(v) => {
+                let data_list = FloatList {
+                    value: tensor::interleave(&v),
+                };
+
                 Kind::FloatList(data_list)
             }
         };
@@ -537,36 +3204,204 @@ impl From<Payload> for Feature {
     }
 }
 
+/// Predict request for `inputs`, asking the server for only the `output_filter` outputs
+fn predict_request(
+    model_spec: ModelSpec,
+    mut inputs: HashMap<String, TensorProto>,
+    output_filter: Vec<String>,
+    tensor_content: bool,
+) -> PredictRequest {
+    if tensor_content {
+        inputs.values_mut().for_each(pack_tensor_content);
+    }
+    PredictRequest {
+        model_spec: Some(model_spec),
+        inputs,
+        output_filter,
+    }
+}
+
+/// CRC32 of the encoded input tensors, in input name order
+fn input_checksum(inputs: &HashMap<String, TensorProto>) -> u32 {
+    let mut names: Vec<_> = inputs.keys().collect();
+    names.sort();
+
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = Vec::new();
+    for name in names {
+        buf.clear();
+        inputs[name]
+            .encode(&mut buf)
+            .expect("encoding to a Vec cannot fail");
+        hasher.update(&buf);
+    }
+    hasher.finalize()
+}
+
+/// Log the shape of an outgoing request, without the tensor values
+///
+/// The model spec and each input's data type and shape are logged at debug level, and the
+/// output filter at trace level. Pixel data is never logged, as it can run to megabytes.
+#[cfg(feature = "tracing")]
+fn log_request(request: &PredictRequest) {
+    if let Some(spec) = &request.model_spec {
+        log::debug!(
+            "predict request: model {} version {:?} signature {:?}",
+            spec.name,
+            spec.version_choice,
+            spec.signature_name
+        );
+    }
+
+    let mut names: Vec<_> = request.inputs.keys().collect();
+    names.sort();
+    for name in names {
+        let tensor = &request.inputs[name];
+        let shape: Vec<i64> = tensor
+            .tensor_shape
+            .as_ref()
+            .map(|shape| shape.dim.iter().map(|d| d.size).collect())
+            .unwrap_or_default();
+        log::debug!(
+            "  input {}: {:?} {:?}",
+            name,
+            DataType::from_i32(tensor.dtype).unwrap_or(DataType::DtInvalid),
+            shape
+        );
+    }
+    log::trace!("  output filter: {:?}", request.output_filter);
+}
+
+/// Decode every frame of an animation as an RGBA image
+fn decode_frames<'a, D: AnimationDecoder<'a>>(decoder: D) -> Result<Vec<DynamicImage>> {
+    Ok(decoder
+        .into_frames()
+        .collect_frames()?
+        .into_iter()
+        .map(|frame| DynamicImage::ImageRgba8(frame.into_buffer()))
+        .collect())
+}
+
 trait MapToFeatures {
-    fn to_features(self) -> Features;
+    fn to_features(self, allow_duplicates: bool) -> std::result::Result<Features, InputError>;
 }
 
-impl<S, V> MapToFeatures for HashMap<S, V>
+/// Features are built in iteration order. Repeated names are an error, unless
+/// `allow_duplicates` is set, in which case later entries replace earlier ones.
+impl<I, S, V> MapToFeatures for I
 where
+    I: IntoIterator<Item = (S, V)>,
     S: Into<String>,
     V: Into<Payload>,
 {
-    fn to_features(self) -> Features {
-        let i = self.into_iter().map(|(k, v)| {
-            let payload = v.into();
-            let mut feature = Feature::default();
-
-            match payload {
-                Payload::Bytes(v) => {
-                    feature.kind = Some(feature::Kind::BytesList(BytesList { value: v }));
-                }
-                Payload::Ints(v) => {
-                    feature.kind = Some(feature::Kind::Int64List(Int64List { value: v }));
-                }
-                Payload::Floats(v) => {
-                    feature.kind = Some(feature::Kind::FloatList(FloatList { value: v }));
-                }
+    fn to_features(self, allow_duplicates: bool) -> std::result::Result<Features, InputError> {
+        let mut feature = HashMap::new();
+        for (k, v) in self {
+            let name = k.into();
+            if !allow_duplicates && feature.contains_key(&name) {
+                return Err(InputError::DuplicateFeature(name));
             }
+            feature.insert(name, Feature::from(v.into()));
+        }
+        Ok(Features { feature })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predict_request_carries_output_filter() {
+        let mut inputs = HashMap::new();
+        inputs.insert(
+            "input".to_string(),
+            Payload::Floats(vec![1.0]).into_tensor(&[1]),
+        );
+        let filter = vec!["probabilities".to_string()];
+
+        let request = predict_request(ModelSpec::default(), inputs, filter.clone(), true);
 
-            (k.into(), feature)
+        assert_eq!(request.output_filter, filter);
+        assert!(request.inputs["input"].float_val.is_empty());
+        assert!(!request.inputs["input"].tensor_content.is_empty());
+    }
+
+    #[tokio::test]
+    async fn call_metadata_is_sent_past_the_result_cache_and_micro_batching() {
+        let (mut client, log) = test_server::connect(|builder| {
+            builder
+                .result_cache(4)
+                .micro_batch(Duration::from_millis(1), 8);
+        })
+        .await;
+        let img = DynamicImage::new_rgb8(1, 1);
+        client.predict(img.clone(), "model").await.unwrap();
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-priority", "high".parse().unwrap());
+        client
+            .predict_with_metadata(img, "model", metadata)
+            .await
+            .unwrap();
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.predictions.len(), 2);
+        assert!(log.metadata[0].get("x-priority").is_none());
+        assert_eq!(log.metadata[1].get("x-priority").unwrap(), "high");
+    }
+
+    #[tokio::test]
+    async fn debug_output_redacts_request_ids_and_metadata() {
+        let (mut client, _) = test_server::connect(|builder| {
+            builder.request_id("secret-id");
+        })
+        .await;
+        let mut metadata = MetadataMap::new();
+        metadata.insert("authorization", "Bearer secret-token".parse().unwrap());
+        client.call_metadata = Some(metadata);
+
+        let debug = format!("{:?}", client);
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains(r#"call_metadata: Some({"authorization": <redacted>})"#));
+
+        let mut builder = TensorflowServing::new();
+        builder.request_id("secret-id");
+        assert!(!format!("{:?}", builder).contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn gif_frames_keep_their_alpha_channel() {
+        let (mut client, log) = test_server::connect(|builder| {
+            builder.channels(Channels::Rgba);
+        })
+        .await;
+
+        let frames = (0..2).map(|i| {
+            let pixel = image::Rgba([255, 0, 0, if i == 0 { 0 } else { 255 }]);
+            image::Frame::new(image::RgbaImage::from_pixel(2, 2, pixel))
         });
-        Features {
-            feature: HashMap::from_iter(i),
-        }
+        let path = std::env::temp_dir().join(format!("frames-{}.gif", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        image::codecs::gif::GifEncoder::new(file)
+            .encode_frames(frames)
+            .unwrap();
+
+        let results = client.predict_frames(&path, "model").await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(results.unwrap().len(), 2);
+
+        let log = log.lock().unwrap();
+        let alphas: Vec<f32> = log
+            .predictions
+            .iter()
+            .map(|request| {
+                let tensor = request.inputs.values().next().unwrap();
+                let dims = &tensor.tensor_shape.as_ref().unwrap().dim;
+                assert_eq!(dims.last().unwrap().size, 4);
+                as_floats(tensor).unwrap()[3]
+            })
+            .collect();
+        assert!(alphas[0] < alphas[1]);
     }
 }
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Size and timing information about a single request
+///
+/// Sizes are the protobuf encoded lengths of the messages, which is useful for spotting
+/// requests approaching the gRPC message size limit.
+#[derive(Debug, Clone)]
+pub struct RequestStats {
+    /// Name of the RPC method, e.g. "predict"
+    pub method: &'static str,
+    /// Request ID sent with the request, if any
+    pub request_id: Option<String>,
+    /// Encoded size of the request message in bytes
+    pub request_bytes: usize,
+    /// Encoded size of the response message in bytes
+    pub response_bytes: usize,
+    /// Time taken for the round trip
+    pub elapsed: Duration,
+}
+
+/// Callback invoked with the stats of every completed request
+pub type Observer = Arc<dyn Fn(&RequestStats) + Send + Sync>;
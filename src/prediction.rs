@@ -0,0 +1,476 @@
+use crate::{
+    as_floats, as_int64s, DataType, PredictResponse, Result, SignatureDef, TensorProto,
+    TensorflowServingError,
+};
+use image::DynamicImage;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Index;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Result of an image prediction
+///
+/// Built from the `probabilities` and `classes` outputs of a `PredictResponse`, as returned by
+/// standard image classification models.
+#[derive(Debug, Clone)]
+pub struct PredictionResult {
+    /// Probability of each class
+    pub probabilities: Vec<f32>,
+    /// Index of the most likely class
+    pub max_idx: usize,
+    labels: Option<Arc<Vec<String>>>,
+    outputs: HashMap<String, TensorProto>,
+}
+
+impl PredictionResult {
+    /// Build a `PredictionResult` from the raw server response
+    ///
+    /// Returns an error if the `probabilities` or `classes` outputs are missing, or if
+    /// `classes` is not a single `DT_INT64` (or `DT_INT32`) value.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{Payload, PredictResponse, PredictionResult};
+    /// let mut response = PredictResponse::default();
+    /// let probabilities = Payload::Floats(vec![0.2, 0.8]).into_tensor(&[1, 2]);
+    /// response.outputs.insert("probabilities".into(), probabilities);
+    ///
+    /// let err = PredictionResult::from_raw(response.clone()).unwrap_err();
+    /// assert_eq!(err.to_string(), "classes output missing from response");
+    ///
+    /// let classes = Payload::Ints(vec![1]).into_tensor(&[1]);
+    /// response.outputs.insert("classes".into(), classes);
+    /// let result = PredictionResult::from_raw(response).unwrap();
+    /// assert_eq!(result.probabilities, vec![0.2, 0.8]);
+    /// assert_eq!(result.max_idx, 1);
+    /// ```
+    pub fn from_raw(response: PredictResponse) -> Result<Self> {
+        Self::from_raw_with_keys(response, &OutputKeys::default())
+    }
+
+    /// Build a `PredictionResult` from the raw server response, using the given output names
+    ///
+    /// Returns an error if either of the outputs named by `keys` is missing, or if the classes
+    /// output is not a single, non-negative `DT_INT64` (or `DT_INT32`) value:
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{OutputKeys, Payload, PredictResponse, PredictionResult};
+    /// let mut response = PredictResponse::default();
+    /// let probabilities = Payload::Floats(vec![0.2, 0.8]).into_tensor(&[1, 2]);
+    /// response.outputs.insert("probs".into(), probabilities);
+    /// let keys = OutputKeys {
+    ///     probabilities: "probs".to_string(),
+    ///     classes: "labels".to_string(),
+    /// };
+    /// let with_classes = |classes| {
+    ///     let mut response = response.clone();
+    ///     response.outputs.insert("labels".into(), classes);
+    ///     PredictionResult::from_raw_with_keys(response, &keys)
+    /// };
+    ///
+    /// let err = with_classes(Payload::Floats(vec![1.0]).into_tensor(&[1])).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "labels output has data type DT_FLOAT, expected DT_INT64 or DT_INT32"
+    /// );
+    ///
+    /// let err = with_classes(Payload::Ints(vec![0, 1]).into_tensor(&[2])).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "labels output has shape [2], expected a single value"
+    /// );
+    ///
+    /// let err = with_classes(Payload::Ints(vec![-1]).into_tensor(&[1])).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "labels output has negative class index -1"
+    /// );
+    ///
+    /// let result = with_classes(Payload::Ints(vec![1]).into_tensor(&[1])).unwrap();
+    /// assert_eq!(result.max_idx, 1);
+    /// ```
+    pub fn from_raw_with_keys(response: PredictResponse, keys: &OutputKeys) -> Result<Self> {
+        let outputs = response.outputs;
+
+        let probabilities = outputs
+            .get(&keys.probabilities)
+            .ok_or_else(|| TensorflowServingError::MissingOutput(keys.probabilities.clone()))?;
+        let probabilities = as_floats(probabilities)?;
+
+        let classes = outputs
+            .get(&keys.classes)
+            .ok_or_else(|| TensorflowServingError::MissingOutput(keys.classes.clone()))?;
+        if classes.dtype != DataType::DtInt64 as i32 && classes.dtype != DataType::DtInt32 as i32 {
+            return Err(TensorflowServingError::DtypeMismatch {
+                name: keys.classes.clone(),
+                expected: vec![DataType::DtInt64, DataType::DtInt32],
+                found: DataType::from_i32(classes.dtype).unwrap_or(DataType::DtInvalid),
+            });
+        }
+        let dims: Vec<i64> = classes
+            .tensor_shape
+            .as_ref()
+            .map(|shape| shape.dim.iter().map(|d| d.size).collect())
+            .unwrap_or_default();
+        if dims.iter().product::<i64>() != 1 {
+            return Err(format!(
+                "{} output has shape {:?}, expected a single value",
+                keys.classes, dims
+            )
+            .into());
+        }
+        let max_idx = as_int64s(classes)?
+            .first()
+            .copied()
+            .ok_or_else(|| format!("{} output does not contain a value", keys.classes))?;
+        let max_idx = usize::try_from(max_idx).map_err(|_| {
+            format!(
+                "{} output has negative class index {}",
+                keys.classes, max_idx
+            )
+        })?;
+
+        Ok(PredictionResult {
+            probabilities,
+            max_idx,
+            labels: None,
+            outputs,
+        })
+    }
+
+    /// Label of the most likely class
+    ///
+    /// Only available if the client was configured with a label file (see
+    /// [with_labels_file](struct.TensorflowServingBuilder.html#method.with_labels_file)), and
+    /// the label file contains an entry for the class.
+    pub fn label(&self) -> Option<&str> {
+        self.labels
+            .as_ref()
+            .and_then(|labels| labels.get(self.max_idx))
+            .map(String::as_str)
+    }
+
+    /// Probability of the most likely class
+    pub fn confidence(&self) -> Option<f32> {
+        self.probabilities.get(self.max_idx).copied()
+    }
+
+    /// Output tensor of the response the result was built from
+    pub fn output(&self, name: &str) -> Option<&TensorProto> {
+        self.outputs.get(name)
+    }
+
+    /// Index of the largest value along `axis` of a float output, respecting its shape
+    ///
+    /// Structured outputs, e.g. per-pixel classes shaped `[1, H*W, num_classes]` or sequence
+    /// outputs, need an argmax per row rather than over every value. The argmax is taken along
+    /// `axis`, and the indices are returned in row-major order over the remaining dimensions,
+    /// so for a `[1, 3, 4]` output and axis 2 there is one index per row of 4 values. Returns
+    /// an error if the output is missing, if `axis` is out of range, or if the number of
+    /// values does not match the output shape.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{Payload, PredictResponse, PredictionResult};
+    /// let mut response = PredictResponse::default();
+    /// let outputs = &mut response.outputs;
+    /// outputs.insert("probabilities".into(), Payload::Floats(vec![1.0]).into_tensor(&[1]));
+    /// outputs.insert("classes".into(), Payload::Ints(vec![0]).into_tensor(&[1]));
+    /// let pixels = vec![0.1, 0.9, 0.8, 0.2, 0.3, 0.7];
+    /// outputs.insert("mask".into(), Payload::Floats(pixels).into_tensor(&[1, 3, 2]));
+    ///
+    /// let result = PredictionResult::from_raw(response).unwrap();
+    /// assert_eq!(result.argmax_axis("mask", 2).unwrap(), vec![1, 0, 1]);
+    /// assert_eq!(result.argmax_axis("mask", 1).unwrap(), vec![1, 0]);
+    /// ```
+    pub fn argmax_axis(&self, output_name: &str, axis: usize) -> Result<Vec<usize>> {
+        let tensor = self
+            .output(output_name)
+            .ok_or_else(|| TensorflowServingError::MissingOutput(output_name.to_string()))?;
+        let dims: Vec<usize> = tensor
+            .tensor_shape
+            .as_ref()
+            .map(|shape| shape.dim.iter().map(|d| d.size.max(0) as usize).collect())
+            .unwrap_or_default();
+        if axis >= dims.len() {
+            return Err(format!(
+                "axis {} out of range for {} output with {} dimensions",
+                axis,
+                output_name,
+                dims.len()
+            )
+            .into());
+        }
+
+        let values = as_floats(tensor)?;
+        if values.len() != dims.iter().product::<usize>() {
+            return Err(format!(
+                "{} output has {} values, which does not match its shape {:?}",
+                output_name,
+                values.len(),
+                dims
+            )
+            .into());
+        }
+
+        let outer: usize = dims[..axis].iter().product();
+        let n = dims[axis];
+        let inner: usize = dims[axis + 1..].iter().product();
+        let mut indices = Vec::with_capacity(outer * inner);
+        for o in 0..outer {
+            for i in 0..inner {
+                let value = |k: usize| values[(o * n + k) * inner + i];
+                let max = (0..n).fold(0, |best, k| if value(k) > value(best) { k } else { best });
+                indices.push(max);
+            }
+        }
+        Ok(indices)
+    }
+
+    /// Compare this result with another, e.g. from a different version of the same model
+    ///
+    /// If the results have different numbers of classes, the missing probabilities are
+    /// treated as 0.
+    pub fn diff(&self, other: &Self) -> PredictionDiff {
+        let len = self.probabilities.len().max(other.probabilities.len());
+        let probability = |p: &[f32], i: usize| p.get(i).copied().unwrap_or(0.0);
+        let max_probability_delta = (0..len)
+            .map(|i| {
+                (probability(&self.probabilities, i) - probability(&other.probabilities, i)).abs()
+            })
+            .fold(0.0, f32::max);
+
+        PredictionDiff {
+            class_changed: self.max_idx != other.max_idx,
+            max_probability_delta,
+        }
+    }
+
+    pub(crate) fn with_labels(mut self, labels: Option<Arc<Vec<String>>>) -> Self {
+        self.labels = labels;
+        self
+    }
+}
+
+/// Serialized as a struct with the `probabilities`, `max_idx` and `label` fields, where
+/// `label` is `None` if no label is available (see
+/// [label](struct.PredictionResult.html#method.label)). Requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PredictionResult {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("PredictionResult", 3)?;
+        state.serialize_field("probabilities", &self.probabilities)?;
+        state.serialize_field("max_idx", &self.max_idx)?;
+        state.serialize_field("label", &self.label())?;
+        state.end()
+    }
+}
+
+/// Difference between two prediction results
+///
+/// Returned by [`PredictionResult::diff`](struct.PredictionResult.html#method.diff).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PredictionDiff {
+    /// Whether the most likely class differs
+    pub class_changed: bool,
+    /// Largest absolute difference between the probabilities of any class
+    pub max_probability_delta: f32,
+}
+
+/// Names of the outputs used to build a [`PredictionResult`](struct.PredictionResult.html)
+///
+/// Defaults to `probabilities` and `classes`, the names used by standard image
+/// classification models.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputKeys {
+    /// Name of the float output holding the probability of each class
+    pub probabilities: String,
+    /// Name of the integer output holding the index of the most likely class
+    pub classes: String,
+}
+
+impl Default for OutputKeys {
+    fn default() -> Self {
+        OutputKeys {
+            probabilities: "probabilities".to_string(),
+            classes: "classes".to_string(),
+        }
+    }
+}
+
+impl OutputKeys {
+    /// Infer the output names from a signature definition
+    ///
+    /// The outputs of the signature are considered in alphabetical order of their names. The
+    /// first `DT_FLOAT` output is used for the probabilities, and the first `DT_INT64` or
+    /// `DT_INT32` output for the classes. If the signature has no output of the right type,
+    /// the default name is used for that output.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{DataType, OutputKeys, SignatureDef, TensorInfo};
+    /// let mut signature = SignatureDef::default();
+    /// for (name, dtype) in &[("scores", DataType::DtFloat), ("labels", DataType::DtInt64)] {
+    ///     let info = TensorInfo { dtype: *dtype as i32, ..Default::default() };
+    ///     signature.outputs.insert(name.to_string(), info);
+    /// }
+    ///
+    /// let keys = OutputKeys::from_signature(&signature);
+    /// assert_eq!(keys.probabilities, "scores");
+    /// assert_eq!(keys.classes, "labels");
+    /// ```
+    pub fn from_signature(signature: &SignatureDef) -> Self {
+        let mut names: Vec<_> = signature.outputs.keys().collect();
+        names.sort();
+
+        let first_of = |dtypes: &[DataType]| {
+            names
+                .iter()
+                .find(|name| {
+                    let dtype = signature.outputs[name.as_str()].dtype;
+                    dtypes.iter().any(|&d| d as i32 == dtype)
+                })
+                .map(|name| name.to_string())
+        };
+
+        let defaults = OutputKeys::default();
+        OutputKeys {
+            probabilities: first_of(&[DataType::DtFloat]).unwrap_or(defaults.probabilities),
+            classes: first_of(&[DataType::DtInt64, DataType::DtInt32]).unwrap_or(defaults.classes),
+        }
+    }
+}
+
+/// Collection of prediction results from a batch of inputs
+///
+/// Wraps the individual results with some summary statistics across the batch. Results keep
+/// the order of the inputs, and can be accessed by index.
+#[derive(Debug, Clone, Default)]
+pub struct PredictionBatch {
+    results: Vec<PredictionResult>,
+}
+
+impl PredictionBatch {
+    /// Number of results in the batch
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether the batch contains no results
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Iterate over the results in input order
+    pub fn iter(&self) -> std::slice::Iter<'_, PredictionResult> {
+        self.results.iter()
+    }
+
+    /// Number of results predicting each class, keyed by class index
+    pub fn class_counts(&self) -> HashMap<usize, usize> {
+        let mut counts = HashMap::new();
+        for result in &self.results {
+            *counts.entry(result.max_idx).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Mean probability of the predicted class across the batch
+    ///
+    /// Returns `None` for an empty batch.
+    pub fn mean_confidence(&self) -> Option<f32> {
+        if self.results.is_empty() {
+            return None;
+        }
+
+        let total: f32 = self
+            .results
+            .iter()
+            .map(|r| r.confidence().unwrap_or(0.0))
+            .sum();
+        Some(total / self.results.len() as f32)
+    }
+
+    /// Unwrap the individual results
+    pub fn into_inner(self) -> Vec<PredictionResult> {
+        self.results
+    }
+}
+
+impl From<Vec<PredictionResult>> for PredictionBatch {
+    fn from(results: Vec<PredictionResult>) -> Self {
+        PredictionBatch { results }
+    }
+}
+
+impl Index<usize> for PredictionBatch {
+    type Output = PredictionResult;
+
+    fn index(&self, index: usize) -> &PredictionResult {
+        &self.results[index]
+    }
+}
+
+impl IntoIterator for PredictionBatch {
+    type Item = PredictionResult;
+    type IntoIter = std::vec::IntoIter<PredictionResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PredictionBatch {
+    type Item = &'a PredictionResult;
+    type IntoIter = std::slice::Iter<'a, PredictionResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.iter()
+    }
+}
+
+/// Load a newline delimited label file, where line N contains the label for class N
+pub(crate) fn load_labels<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(contents.lines().map(String::from).collect())
+}
+
+impl PredictResponse {
+    /// Decode an encoded image returned in a `DT_STRING` output
+    ///
+    /// Image-to-image models, e.g. for segmentation, often return their output as PNG or
+    /// JPEG bytes. The first `string_val` of the output named `name` is decoded, with the
+    /// format guessed from its contents. Returns an error if the output is missing or empty,
+    /// or if it does not hold a supported image.
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::{Payload, PredictResponse};
+    /// # use image::GenericImageView;
+    /// let mut png = Vec::new();
+    /// image::DynamicImage::new_rgb8(3, 2)
+    ///     .write_to(&mut png, image::ImageFormat::Png)
+    ///     .unwrap();
+    ///
+    /// let mut response = PredictResponse::default();
+    /// response
+    ///     .outputs
+    ///     .insert("mask".to_string(), Payload::Bytes(vec![png]).into_tensor(&[1]));
+    ///
+    /// let img = response.output_as_image("mask").unwrap();
+    /// assert_eq!((img.width(), img.height()), (3, 2));
+    /// ```
+    pub fn output_as_image(&self, name: &str) -> Result<DynamicImage> {
+        let tensor = self
+            .outputs
+            .get(name)
+            .ok_or_else(|| TensorflowServingError::MissingOutput(name.to_string()))?;
+        let bytes = tensor
+            .string_val
+            .first()
+            .ok_or_else(|| format!("{} output does not contain a value", name))?;
+        Ok(image::load_from_memory(bytes)?)
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{DataType, Image, InputError, Payload, Result, TensorProto};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView};
+use log::info;
+
+/// Memory layout of image tensors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Channels last, `[batch, height, width, channels]`, as used by Tensorflow by default
+    #[default]
+    Nhwc,
+    /// Channels first, `[batch, channels, height, width]`
+    Nchw,
+}
+
+/// Channels images are converted to before building a tensor
+///
+/// Images are converted whatever their source pixel format, so the channel dimension of the
+/// tensor always matches the values sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channels {
+    /// Three channel RGB, dropping any alpha channel
+    #[default]
+    Rgb,
+    /// Single channel luminance
+    Gray,
+    /// Four channel RGBA, with an opaque alpha channel added to images without one
+    Rgba,
+}
+
+impl Channels {
+    /// Number of channels, i.e. the size of the channel dimension of the tensor
+    pub fn count(self) -> usize {
+        match self {
+            Channels::Rgb => 3,
+            Channels::Gray => 1,
+            Channels::Rgba => 4,
+        }
+    }
+}
+
+/// How non-finite values produced by preprocessing are handled
+///
+/// A preprocessing function with a bug, e.g. dividing by a zero standard deviation, can turn
+/// pixel values into NaN or infinity, which the server may reject or silently turn into
+/// meaningless outputs. Values are checked after preprocessing, before the tensor is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Return an [`InputError::NonFiniteInput`](enum.InputError.html) for the first
+    /// non-finite value, so preprocessing bugs surface early
+    #[default]
+    Error,
+    /// Replace NaN with zero, and infinities with the largest finite value of the same sign
+    Clamp,
+}
+
+impl NonFinitePolicy {
+    fn apply(self, pixels: &mut [f32]) -> Result<()> {
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            if pixel.is_finite() {
+                continue;
+            }
+            match self {
+                NonFinitePolicy::Error => {
+                    return Err(InputError::NonFiniteInput {
+                        index,
+                        value: *pixel,
+                    }
+                    .into())
+                }
+                NonFinitePolicy::Clamp if pixel.is_nan() => *pixel = 0.0,
+                NonFinitePolicy::Clamp => *pixel = pixel.clamp(f32::MIN, f32::MAX),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Image conversions applied by the client before building a tensor
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ImageOptions {
+    /// Channels to convert images to
+    pub(crate) channels: Channels,
+    /// Memory layout of image tensors
+    pub(crate) layout: Layout,
+    /// Downscale images whose width or height exceeds this many pixels
+    pub(crate) max_dimension: Option<u32>,
+    /// Resize images to this width and height, with the filter
+    pub(crate) resize: Option<(u32, u32, FilterType)>,
+    /// Resize images to this `[height, width, channels]` shape
+    pub(crate) input_shape: Option<[u32; 3]>,
+    /// Per channel `(mean, std)` to normalize preprocessed values with
+    pub(crate) normalize: Option<([f32; 3], [f32; 3])>,
+    /// Handling of NaN and infinite values after preprocessing
+    pub(crate) non_finite: NonFinitePolicy,
+}
+
+/// Build the tensor the client sends for an image, without sending it
+///
+/// Applies `preprocessing_fn` to every pixel value, and arranges the values according to
+/// `layout`. Supported data types are:
+///
+/// * `DataType::DtFloat`: the preprocessed values are sent as floats
+/// * `DataType::DtUint8`: the preprocessed values are rounded and clamped to 0-255
+/// * `DataType::DtUint16`: the preprocessed values are rounded and clamped to 0-65535, for
+///   models taking 16-bit images
+///
+/// Values normalized with the client's
+/// [normalize](struct.TensorflowServingBuilder.html#method.normalize) option can only be sent
+/// as floats, so integer data types return an
+/// [`InputError::NormalizedIntegerInput`](enum.InputError.html) with it.
+///
+/// Image tensors are shaped `[1, height, width, channels]` in the default NHWC layout:
+///
+/// ```rust
+/// # use tensorflow_serving::{image_to_tensor, DataType, Layout};
+/// let img = image::DynamicImage::new_rgb8(4, 2);
+/// let tensor = image_to_tensor(img, |p| p, Layout::Nhwc, DataType::DtFloat).unwrap();
+/// let dims: Vec<i64> = tensor.tensor_shape.unwrap().dim.iter().map(|d| d.size).collect();
+/// assert_eq!(dims, vec![1, 2, 4, 3]);
+/// ```
+///
+/// Images are sent with three channels, so e.g. the alpha channel of an RGBA PNG is dropped,
+/// unless the client is configured with other
+/// [channels](struct.TensorflowServingBuilder.html#method.channels):
+///
+/// ```rust
+/// # use tensorflow_serving::{image_to_tensor, DataType, Layout};
+/// let img = image::DynamicImage::new_rgba8(2, 2);
+/// let tensor = image_to_tensor(img, |p| p, Layout::Nhwc, DataType::DtFloat).unwrap();
+/// assert_eq!(tensor.float_val.len(), 2 * 2 * 3);
+/// ```
+///
+/// Preprocessing must produce finite values, as NaN or infinity is returned as an error:
+///
+/// ```rust
+/// # use tensorflow_serving::{image_to_tensor, DataType, InputError, Layout};
+/// # use tensorflow_serving::TensorflowServingError;
+/// let img = image::DynamicImage::new_rgb8(2, 2);
+/// let std_dev = 0.0;
+/// let err = image_to_tensor(img, |p| (p - 127.5) / std_dev, Layout::Nhwc, DataType::DtFloat)
+///     .unwrap_err();
+/// assert!(matches!(
+///     err,
+///     TensorflowServingError::Input(InputError::NonFiniteInput { index: 0, .. })
+/// ));
+/// ```
+///
+/// 16-bit images keep their full range of sample values, so `preprocessing_fn` sees values
+/// up to 65535 rather than 255:
+///
+/// ```rust
+/// # use tensorflow_serving::{image_to_tensor, DataType, Layout};
+/// # use image::{png::PngEncoder, ColorType};
+/// // PNG stores 16-bit samples big-endian
+/// let samples: Vec<u8> = [1000u16, 2000, 40000]
+///     .iter()
+///     .cycle()
+///     .take(2 * 2 * 3)
+///     .flat_map(|s| s.to_be_bytes().to_vec())
+///     .collect();
+/// let mut png = Vec::new();
+/// PngEncoder::new(&mut png)
+///     .encode(&samples, 2, 2, ColorType::Rgb16)
+///     .unwrap();
+///
+/// let decoded = image::load_from_memory(&png).unwrap();
+/// let tensor = image_to_tensor(decoded, |p| p, Layout::Nhwc, DataType::DtFloat).unwrap();
+/// assert_eq!(&tensor.float_val[..3], &[1000.0, 2000.0, 40000.0]);
+/// ```
+///
+/// This is useful for testing preprocessing, and for precomputing tensors offline.
+pub fn image_to_tensor<I, M>(
+    img: I,
+    preprocessing_fn: M,
+    layout: Layout,
+    dtype: DataType,
+) -> Result<TensorProto>
+where
+    I: Image,
+    M: Fn(f32) -> f32,
+{
+    to_tensor(
+        img,
+        preprocessing_fn,
+        layout,
+        dtype,
+        &ImageOptions::default(),
+    )
+}
+
+/// Build an image tensor, applying the client image options first
+pub(crate) fn to_tensor<I, M>(
+    img: I,
+    preprocessing_fn: M,
+    layout: Layout,
+    dtype: DataType,
+    options: &ImageOptions,
+) -> Result<TensorProto>
+where
+    I: Image,
+    M: Fn(f32) -> f32,
+{
+    if options.normalize.is_some() && dtype != DataType::DtFloat {
+        return Err(InputError::NormalizedIntegerInput(dtype).into());
+    }
+
+    // Load data
+    let mut img = img.to_image()?;
+    if let Some(max) = options.max_dimension {
+        let (width, height) = img.dimensions();
+        if width > max || height > max {
+            img = img.resize(max, max, FilterType::Triangle);
+            let (new_width, new_height) = img.dimensions();
+            info!(
+                "downscaled {}x{} image to {}x{} to fit the maximum input dimension of {}",
+                width, height, new_width, new_height, max
+            );
+        }
+    }
+
+    if let Some((width, height, filter)) = options.resize {
+        img = img.resize_exact(width, height, filter);
+    }
+
+    if options.channels == Channels::Gray {
+        img = img.grayscale();
+    }
+    let channels = options.channels.count();
+
+    if let Some([height, width, expected_channels]) = options.input_shape {
+        if expected_channels as usize != channels {
+            return Err(InputError::ShapeMismatch {
+                expected: vec![height.into(), width.into(), expected_channels.into()],
+                found: vec![height.into(), width.into(), channels as i64],
+            }
+            .into());
+        }
+        img = img.resize_exact(width, height, FilterType::Triangle);
+    }
+
+    let (width, height) = img.dimensions();
+    let mut pixels: Vec<_> = samples(&img, options.channels)
+        .into_iter()
+        .map(preprocessing_fn)
+        .collect();
+    if let Some((mean, std)) = options.normalize {
+        normalize(&mut pixels, channels, mean, std);
+    }
+    options.non_finite.apply(&mut pixels)?;
+
+    let (pixels, dims) = match layout {
+        Layout::Nhwc => (pixels, [1, height as i64, width as i64, channels as i64]),
+        Layout::Nchw => (
+            to_channels_first(&pixels, channels),
+            [1, channels as i64, height as i64, width as i64],
+        ),
+    };
+
+    match dtype {
+        DataType::DtFloat => Ok(Payload::Floats(pixels).into_tensor(&dims)),
+        DataType::DtUint8 => {
+            let mut tensor = Payload::Floats(Vec::new()).into_tensor(&dims);
+            tensor.dtype = DataType::DtUint8 as i32;
+            tensor.int_val = pixels
+                .iter()
+                .map(|p| p.round().clamp(0.0, 255.0) as i32)
+                .collect();
+            Ok(tensor)
+        }
+        DataType::DtUint16 => {
+            let values = pixels
+                .iter()
+                .map(|p| p.round().clamp(0.0, 65535.0) as u16)
+                .collect();
+            Ok(Payload::UInt16(values).into_tensor(&dims))
+        }
+        other => Err(format!("unsupported image tensor data type {:?}", other).into()),
+    }
+}
+
+/// Interleaved sample values of an image, keeping the full range of 16-bit images
+///
+/// The image is converted to the requested channels, so the number of values always matches
+/// the declared shape whatever the source pixel format, e.g. RGBA or BGR. 16-bit images are
+/// converted to `u16` buffers, rather than being truncated to 8-bit pixels.
+fn samples(img: &DynamicImage, channels: Channels) -> Vec<f32> {
+    fn widen<T: Copy + Into<f32>>(samples: &[T]) -> Vec<f32> {
+        samples.iter().map(|&s| s.into()).collect()
+    }
+
+    match (channels, is_wide(img)) {
+        (Channels::Gray, false) => widen(img.to_luma8().as_raw()),
+        (Channels::Gray, true) => widen(img.to_luma16().as_raw()),
+        (Channels::Rgb, false) => widen(img.to_rgb8().as_raw()),
+        (Channels::Rgb, true) => widen(img.to_rgb16().as_raw()),
+        (Channels::Rgba, false) => widen(img.to_rgba8().as_raw()),
+        (Channels::Rgba, true) => widen(img.to_rgba16().as_raw()),
+    }
+}
+
+/// Whether the image has 16-bit samples
+fn is_wide(img: &DynamicImage) -> bool {
+    let color = img.color();
+    color.bytes_per_pixel() > color.channel_count()
+}
+
+/// Largest sample value of the image's bit depth, 255 or 65535
+pub(crate) fn sample_max(img: &DynamicImage) -> f32 {
+    if is_wide(img) {
+        f32::from(u16::MAX)
+    } else {
+        f32::from(u8::MAX)
+    }
+}
+
+/// Normalize interleaved (channels last) values by the mean and standard deviation of their
+/// channel
+///
+/// Single channel values use the first mean and standard deviation, and the alpha channel of
+/// RGBA values is left as it is.
+fn normalize(pixels: &mut [f32], channels: usize, mean: [f32; 3], std: [f32; 3]) {
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let channel = i % channels;
+        if channel < 3 {
+            *pixel = (*pixel - mean[channel]) / std[channel];
+        }
+    }
+}
+
+/// Reorder interleaved (channels last) pixel values so each channel is contiguous
+fn to_channels_first(pixels: &[f32], channels: usize) -> Vec<f32> {
+    let n_pixels = pixels.len() / channels;
+    let mut out = Vec::with_capacity(pixels.len());
+    for c in 0..channels {
+        out.extend((0..n_pixels).map(|p| pixels[p * channels + c]));
+    }
+    out
+}
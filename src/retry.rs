@@ -0,0 +1,162 @@
+use rand::Rng;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+/// Policy for retrying calls after transport failures
+///
+/// When a call fails because the connection to the server was lost (e.g. the server was
+/// restarted during a deploy), the client re-establishes its channel using the original
+/// endpoint configuration before the next attempt. Up to `max_retries` further attempts are
+/// made, waiting `initial_backoff` before the first and doubling the wait for each attempt
+/// after that, up to `max_backoff`. The wait is randomised according to `jitter`, so that
+/// many clients losing their connection at once do not retry in lockstep.
+///
+/// Calls rejected with `ResourceExhausted`, as returned when the server's batch queue is full,
+/// are also retried with the same backoff, but keep their channel as it is still healthy.
+/// Repeated rejections also apply backpressure: each one makes every new call, across all
+/// clones of the client, wait for a further step of the backoff before it is sent, and each
+/// successful call takes one step off again. A micro batch counts as a single call, so it is
+/// delayed as a whole. Other errors returned by the server itself, such as an invalid
+/// argument, are never retried.
+///
+/// ```rust
+/// # use tensorflow_serving::RetryPolicy;
+/// # use std::time::Duration;
+/// # use tensorflow_serving::Jitter;
+/// let policy = RetryPolicy {
+///     max_retries: 3,
+///     initial_backoff: Duration::from_millis(50),
+///     jitter: Jitter::None,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of attempts to make after the first one fails
+    pub max_retries: u32,
+    /// Time to wait before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the time to wait between retries
+    pub max_backoff: Duration,
+    /// How the wait between retries is randomised
+    pub jitter: Jitter,
+}
+
+/// Randomisation of the wait between retries
+///
+/// Each strategy is applied to the exponential backoff for the attempt, after capping it at
+/// the maximum backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Jitter {
+    /// Wait a random time between zero and the backoff
+    ///
+    /// This spreads retries out the most, and is the default.
+    #[default]
+    Full,
+    /// Wait half the backoff, plus a random time up to the other half
+    Equal,
+    /// Wait exactly the backoff, e.g. for deterministic tests
+    None,
+}
+
+impl Default for RetryPolicy {
+    /// No retries, so failures are returned to the caller straight away
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: Jitter::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Time to wait before retry number `attempt`, counting from zero
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        let backoff = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        let mut rng = rand::thread_rng();
+        match self.jitter {
+            Jitter::Full => backoff.mul_f64(rng.gen()),
+            Jitter::Equal => backoff / 2 + (backoff / 2).mul_f64(rng.gen()),
+            Jitter::None => backoff,
+        }
+    }
+}
+
+/// Whether a failed call indicates the channel to the server is broken
+///
+/// tonic reports connection failures either as `Unavailable`, or as `Unknown` with the
+/// underlying transport error as the message.
+pub(crate) fn is_transport_failure(status: &Status) -> bool {
+    match status.code() {
+        Code::Unavailable => true,
+        Code::Unknown => {
+            let message = status.message().to_lowercase();
+            [
+                "not ready",
+                "transport",
+                "broken pipe",
+                "connection",
+                "closed",
+            ]
+            .iter()
+            .any(|needle| message.contains(needle))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a failed call was rejected because the server is overloaded
+pub(crate) fn is_resource_exhausted(status: &Status) -> bool {
+    status.code() == Code::ResourceExhausted
+}
+
+/// Highest backpressure level, bounding the delay to the policy's maximum backoff
+const MAX_BACKPRESSURE: u32 = 8;
+
+/// Adaptive delay applied to new calls while the server reports it is overloaded
+///
+/// Every `ResourceExhausted` response raises the level by one, and every successful call
+/// lowers it by one. While the level is above zero, each new call first waits for the retry
+/// policy's backoff for that level, so repeated rejections progressively reduce the number of
+/// requests in flight, and the delay falls away again as calls succeed. The level is shared
+/// between clones of the client, so all callers slow down together.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Backpressure {
+    level: Arc<AtomicU32>,
+}
+
+impl Backpressure {
+    /// Time to wait before starting a new call, if the server has been overloaded
+    pub(crate) fn delay(&self, policy: &RetryPolicy) -> Option<Duration> {
+        match self.level.load(Ordering::SeqCst) {
+            0 => None,
+            level => Some(policy.backoff(level - 1)),
+        }
+    }
+
+    pub(crate) fn record_exhausted(&self) {
+        let _ = self
+            .level
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |level| {
+                Some((level + 1).min(MAX_BACKPRESSURE))
+            });
+    }
+
+    pub(crate) fn record_success(&self) {
+        let _ = self
+            .level
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |level| {
+                level.checked_sub(1)
+            });
+    }
+}
@@ -0,0 +1,133 @@
+use crate::tensorflow::tensorflow_serving::{
+    ClassificationRequest, ClassificationResponse, GetModelMetadataRequest,
+    GetModelMetadataResponse, MultiInferenceRequest, MultiInferenceResponse, PredictRequest,
+    PredictResponse, RegressionRequest, RegressionResponse,
+};
+use prost::Message;
+use std::sync::Arc;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Channel;
+use tonic::{Request, Response, Status};
+
+/// Fully qualified name of the standard prediction service
+pub(crate) const PREDICTION_SERVICE: &str = "tensorflow.serving.PredictionService";
+
+/// Client for the prediction service, under a configurable service name
+///
+/// Equivalent to the generated `PredictionServiceClient`, except the service name in the
+/// request paths can be changed for servers exposing the service under another package.
+#[derive(Debug, Clone)]
+pub(crate) struct PredictionService {
+    inner: tonic::client::Grpc<Channel>,
+    service: Arc<str>,
+}
+
+impl PredictionService {
+    pub(crate) fn new(channel: Channel, service: Arc<str>) -> Self {
+        PredictionService {
+            inner: tonic::client::Grpc::new(channel),
+            service,
+        }
+    }
+
+    pub(crate) fn service(&self) -> Arc<str> {
+        self.service.clone()
+    }
+
+    pub(crate) async fn classify(
+        &mut self,
+        request: Request<ClassificationRequest>,
+    ) -> Result<Response<ClassificationResponse>, Status> {
+        self.unary("Classify", request).await
+    }
+
+    pub(crate) async fn regress(
+        &mut self,
+        request: Request<RegressionRequest>,
+    ) -> Result<Response<RegressionResponse>, Status> {
+        self.unary("Regress", request).await
+    }
+
+    pub(crate) async fn predict(
+        &mut self,
+        request: Request<PredictRequest>,
+    ) -> Result<Response<PredictResponse>, Status> {
+        self.unary("Predict", request).await
+    }
+
+    pub(crate) async fn multi_inference(
+        &mut self,
+        request: Request<MultiInferenceRequest>,
+    ) -> Result<Response<MultiInferenceResponse>, Status> {
+        self.unary("MultiInference", request).await
+    }
+
+    pub(crate) async fn get_model_metadata(
+        &mut self,
+        request: Request<GetModelMetadataRequest>,
+    ) -> Result<Response<GetModelMetadataResponse>, Status> {
+        self.unary("GetModelMetadata", request).await
+    }
+
+    async fn unary<Req, Resp>(
+        &mut self,
+        method: &str,
+        request: Request<Req>,
+    ) -> Result<Response<Resp>, Status>
+    where
+        Req: Message + Send + Sync + 'static,
+        Resp: Message + Default + Send + Sync + 'static,
+    {
+        self.inner.ready().await.map_err(|e| {
+            Status::new(
+                tonic::Code::Unknown,
+                format!("Service was not ready: {}", e),
+            )
+        })?;
+        let path = method_path(&self.service, method)
+            .map_err(|e| Status::new(tonic::Code::Internal, e))?;
+        let codec = tonic::codec::ProstCodec::default();
+        self.inner.unary(request, path, codec).await
+    }
+}
+
+/// Request path of a method of the service
+pub(crate) fn method_path(service: &str, method: &str) -> Result<PathAndQuery, String> {
+    format!("/{}/{}", service, method)
+        .parse()
+        .map_err(|e| format!("invalid service name {}: {}", service, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, RENAMED_SERVICE};
+    use image::DynamicImage;
+
+    #[test]
+    fn method_path_uses_the_service_name() {
+        let path = method_path("my.pkg.PredictionService", "Predict").unwrap();
+        assert_eq!(path.as_str(), "/my.pkg.PredictionService/Predict");
+    }
+
+    #[test]
+    fn method_path_rejects_invalid_service_names() {
+        let err = method_path("my pkg.PredictionService", "Predict").unwrap_err();
+        assert!(err.starts_with("invalid service name my pkg.PredictionService"));
+    }
+
+    #[tokio::test]
+    async fn predictions_are_sent_to_the_renamed_service() {
+        let (mut client, log) = test_server::connect(|builder| {
+            builder.prediction_service(RENAMED_SERVICE);
+        })
+        .await;
+
+        let result = client.predict(DynamicImage::new_rgb8(1, 1), "model").await;
+        assert_eq!(result.unwrap().max_idx, 1);
+        assert_eq!(
+            log.lock().unwrap().renamed_paths,
+            vec!["/my.pkg.PredictionService/Predict"]
+        );
+    }
+}
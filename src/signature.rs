@@ -0,0 +1,75 @@
+use crate::{
+    AnyRegistry, GetModelMetadataResponse, InputError, Result, SignatureDef, SignatureDefMap,
+    TensorProto,
+};
+use prost::Message;
+use std::collections::HashMap;
+
+/// Decode the signature definitions from a model metadata response
+pub(crate) fn signature_defs(
+    metadata: &GetModelMetadataResponse,
+) -> Result<HashMap<String, SignatureDef>> {
+    let any = metadata
+        .metadata
+        .get("signature_def")
+        .ok_or("signature_def missing from model metadata")?;
+    // The signature_def entry is always a SignatureDefMap, even without a type URL
+    let map: SignatureDefMap = if any.type_url.is_empty() {
+        SignatureDefMap::decode(any.value.as_slice())?
+    } else {
+        AnyRegistry::default().decode_as(any)?
+    };
+    Ok(map.signature_def)
+}
+
+/// Find the named signature in a model metadata response
+///
+/// Returns an error listing the available signatures if it is not present.
+pub(crate) fn find_signature(
+    metadata: &GetModelMetadataResponse,
+    signature_name: &str,
+) -> Result<SignatureDef> {
+    let mut signatures = signature_defs(metadata)?;
+    match signatures.remove(signature_name) {
+        Some(signature) => Ok(signature),
+        None => {
+            let mut available: Vec<_> = signatures.keys().cloned().collect();
+            available.sort();
+            Err(format!(
+                "signature {} not found, available signatures: {}",
+                signature_name,
+                available.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
+/// Check the provided inputs against those expected by a signature
+pub(crate) fn validate_inputs(
+    signature: &SignatureDef,
+    inputs: &HashMap<String, TensorProto>,
+) -> std::result::Result<(), InputError> {
+    let mut missing: Vec<String> = signature
+        .inputs
+        .keys()
+        .filter(|name| !inputs.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut unexpected: Vec<String> = inputs
+        .keys()
+        .filter(|name| !signature.inputs.contains_key(*name))
+        .cloned()
+        .collect();
+
+    if missing.is_empty() && unexpected.is_empty() {
+        return Ok(());
+    }
+
+    missing.sort();
+    unexpected.sort();
+    Err(InputError::InputMismatch {
+        missing,
+        unexpected,
+    })
+}
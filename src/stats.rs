@@ -0,0 +1,64 @@
+use crate::TensorProto;
+
+/// Summary of the values of one channel of an input tensor
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelStats {
+    /// Mean value
+    pub mean: f32,
+    /// Minimum value
+    pub min: f32,
+    /// Maximum value
+    pub max: f32,
+}
+
+/// Per-channel summary of an input tensor
+///
+/// See [predict_with_stats](struct.TensorflowServing.html#method.predict_with_stats).
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputStats {
+    /// Statistics for each channel, in channel order
+    pub channels: Vec<ChannelStats>,
+}
+
+impl InputStats {
+    /// Summarise the float values of a tensor
+    ///
+    /// The channel is taken to be the last dimension of the tensor shape, as in the NHWC
+    /// layout used for images.
+    pub fn from_tensor(tensor: &TensorProto) -> Self {
+        let n_channels = tensor
+            .tensor_shape
+            .as_ref()
+            .and_then(|shape| shape.dim.last())
+            .map(|d| d.size.max(1) as usize)
+            .unwrap_or(1);
+
+        let channels = (0..n_channels)
+            .map(|c| {
+                let values = tensor.float_val.iter().skip(c).step_by(n_channels);
+                let mut count = 0;
+                let mut sum = 0.0f64;
+                let mut min = f32::INFINITY;
+                let mut max = f32::NEG_INFINITY;
+                for v in values {
+                    count += 1;
+                    sum += f64::from(*v);
+                    min = min.min(*v);
+                    max = max.max(*v);
+                }
+
+                ChannelStats {
+                    mean: if count == 0 {
+                        0.0
+                    } else {
+                        (sum / f64::from(count)) as f32
+                    },
+                    min,
+                    max,
+                }
+            })
+            .collect();
+
+        InputStats { channels }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::tensorflow::tensorflow_serving::model_version_status::State;
+use crate::ModelVersionStatus;
+
+/// Which versions of a model to report the status of
+///
+/// Used with
+/// [model_status_with_policy](struct.TensorflowServing.html#method.model_status_with_policy).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Every version known to the server
+    All,
+    /// Only the highest version number
+    Latest,
+    /// Only the given version number
+    Specific(i64),
+    /// Only the version the label is assigned to
+    ///
+    /// Version statuses do not include labels, so the label is resolved by the server.
+    Label(String),
+}
+
+impl VersionPolicy {
+    /// Keep only the version statuses selected by the policy
+    ///
+    /// Labels are resolved by the server, so no filtering is done for `Label`.
+    pub(crate) fn filter(&self, versions: &mut Vec<ModelVersionStatus>) {
+        match self {
+            VersionPolicy::All | VersionPolicy::Label(_) => {}
+            VersionPolicy::Latest => {
+                if let Some(latest) = versions.iter().map(|v| v.version).max() {
+                    versions.retain(|v| v.version == latest);
+                }
+            }
+            VersionPolicy::Specific(version) => versions.retain(|v| v.version == *version),
+        }
+    }
+}
+
+impl ModelVersionStatus {
+    /// Whether the version is loaded and serving requests
+    ///
+    /// ```rust
+    /// # use tensorflow_serving::ModelVersionStatus;
+    /// // 30 is the AVAILABLE state
+    /// let status = ModelVersionStatus { version: 1, state: 30, status: None };
+    /// assert!(status.is_available());
+    /// ```
+    pub fn is_available(&self) -> bool {
+        self.state == State::Available as i32
+    }
+}
@@ -0,0 +1,346 @@
+use std::convert::TryFrom;
+
+use crate::{
+    tensor_shape_proto, DataType, InputError, Payload, Result, TensorInfo, TensorProto,
+    TensorShapeProto,
+};
+
+/// Trait for values which can be converted to a float tensor, inferring the tensor shape
+///
+/// Implemented for `f32` scalars and arbitrarily nested `Vec`s of them, so `Vec<f32>`,
+/// `Vec<Vec<f32>>` etc. can be sent directly. All inner vectors at the same depth must have
+/// the same length, otherwise an [`InputError::ShapeMismatch`](enum.InputError.html) is
+/// returned.
+///
+/// ```rust
+/// # use tensorflow_serving::NestedTensor;
+/// let values = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+/// assert_eq!(values.shape().unwrap(), vec![2, 3]);
+///
+/// let ragged = vec![vec![1.0, 2.0, 3.0], vec![4.0]];
+/// assert!(ragged.shape().is_err());
+/// ```
+pub trait NestedTensor {
+    /// Shape of the tensor
+    ///
+    fn shape(&self) -> std::result::Result<Vec<i64>, InputError>;
+
+    /// Append the values to `out` in row-major order
+    ///
+    fn flatten_into(&self, out: &mut Vec<f32>);
+
+    /// Build a `DT_FLOAT` tensor from the values
+    ///
+    fn to_tensor(&self) -> Result<TensorProto> {
+        let shape = self.shape()?;
+        let mut values = Vec::new();
+        self.flatten_into(&mut values);
+        Ok(Payload::Floats(values).into_tensor(&shape))
+    }
+}
+
+impl NestedTensor for f32 {
+    fn shape(&self) -> std::result::Result<Vec<i64>, InputError> {
+        Ok(Vec::new())
+    }
+
+    fn flatten_into(&self, out: &mut Vec<f32>) {
+        out.push(*self);
+    }
+}
+
+impl<T: NestedTensor> NestedTensor for Vec<T> {
+    fn shape(&self) -> std::result::Result<Vec<i64>, InputError> {
+        self.as_slice().shape()
+    }
+
+    fn flatten_into(&self, out: &mut Vec<f32>) {
+        self.as_slice().flatten_into(out)
+    }
+}
+
+impl<T: NestedTensor> NestedTensor for [T] {
+    fn shape(&self) -> std::result::Result<Vec<i64>, InputError> {
+        let mut inner_shapes = self.iter().map(NestedTensor::shape);
+        let inner = match inner_shapes.next() {
+            Some(shape) => shape?,
+            None => Vec::new(),
+        };
+
+        for shape in inner_shapes {
+            let shape = shape?;
+            if shape != inner {
+                return Err(InputError::ShapeMismatch {
+                    expected: inner,
+                    found: shape,
+                });
+            }
+        }
+
+        let mut shape = vec![self.len() as i64];
+        shape.extend(inner);
+        Ok(shape)
+    }
+
+    fn flatten_into(&self, out: &mut Vec<f32>) {
+        for value in self {
+            value.flatten_into(out);
+        }
+    }
+}
+
+/// Raw bytes of each `string_val` entry of a `DT_STRING` tensor
+pub fn as_string_vec(tensor: &TensorProto) -> Vec<Vec<u8>> {
+    tensor.string_val.clone()
+}
+
+/// Decode each `string_val` entry of a `DT_STRING` tensor as UTF-8
+///
+/// Returns an error if any entry is not valid UTF-8. See
+/// [as_utf8_strings_lossy](fn.as_utf8_strings_lossy.html) to replace invalid sequences
+/// instead.
+///
+/// ```rust
+/// # use tensorflow_serving::{as_utf8_strings, as_utf8_strings_lossy, TensorProto};
+/// let tensor = TensorProto {
+///     string_val: vec!["chat".into(), vec![0xff]],
+///     ..Default::default()
+/// };
+/// assert!(as_utf8_strings(&tensor).is_err());
+/// assert_eq!(as_utf8_strings_lossy(&tensor), vec!["chat", "\u{fffd}"]);
+/// ```
+pub fn as_utf8_strings(tensor: &TensorProto) -> Result<Vec<String>> {
+    tensor
+        .string_val
+        .iter()
+        .map(|s| String::from_utf8(s.clone()).map_err(From::from))
+        .collect()
+}
+
+/// Decode each `string_val` entry of a `DT_STRING` tensor as UTF-8, replacing invalid
+/// sequences with `U+FFFD REPLACEMENT CHARACTER`
+pub fn as_utf8_strings_lossy(tensor: &TensorProto) -> Vec<String> {
+    tensor
+        .string_val
+        .iter()
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect()
+}
+
+/// Move the values of a numeric tensor into the packed `tensor_content` bytes
+///
+/// `DT_FLOAT`, `DT_DOUBLE`, `DT_INT32` and `DT_INT64` values are packed little-endian, which
+/// is more compact than the typed value fields for large tensors. Tensors of other types are
+/// left unchanged. Use [as_floats](fn.as_floats.html) and [as_int64s](fn.as_int64s.html) to
+/// read values back from either encoding:
+///
+/// ```rust
+/// # use tensorflow_serving::{as_floats, pack_tensor_content, Payload};
+/// let mut tensor = Payload::Floats(vec![1.5, -2.0, 1e-8]).into_tensor(&[3]);
+/// pack_tensor_content(&mut tensor);
+/// assert!(tensor.float_val.is_empty());
+/// assert_eq!(tensor.tensor_content.len(), 3 * 4);
+/// assert_eq!(as_floats(&tensor).unwrap(), vec![1.5, -2.0, 1e-8]);
+/// ```
+pub fn pack_tensor_content(tensor: &mut TensorProto) {
+    let content: Vec<u8> = match DataType::from_i32(tensor.dtype) {
+        Some(DataType::DtFloat) => std::mem::take(&mut tensor.float_val)
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect(),
+        Some(DataType::DtDouble) => std::mem::take(&mut tensor.double_val)
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect(),
+        Some(DataType::DtInt32) => std::mem::take(&mut tensor.int_val)
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect(),
+        Some(DataType::DtInt64) => std::mem::take(&mut tensor.int64_val)
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect(),
+        _ => return,
+    };
+    tensor.tensor_content = content;
+}
+
+/// Float values of a tensor, from `tensor_content` if it is set and `float_val` otherwise
+///
+/// Returns an error if `tensor_content` is not a whole number of little-endian `f32`s.
+pub fn as_floats(tensor: &TensorProto) -> Result<Vec<f32>> {
+    if tensor.tensor_content.is_empty() {
+        return Ok(tensor.float_val.clone());
+    }
+    unpack(&tensor.tensor_content, f32::from_le_bytes)
+}
+
+/// Integer values of a `DT_INT64` or `DT_INT32` tensor, widened to `i64`
+///
+/// Values are read from `tensor_content` if it is set, and from `int64_val` or `int_val`
+/// otherwise. Returns an error if `tensor_content` does not hold whole values.
+pub fn as_int64s(tensor: &TensorProto) -> Result<Vec<i64>> {
+    if tensor.tensor_content.is_empty() {
+        if tensor.int64_val.is_empty() {
+            return Ok(tensor.int_val.iter().map(|&v| i64::from(v)).collect());
+        }
+        return Ok(tensor.int64_val.clone());
+    }
+    if tensor.dtype == DataType::DtInt32 as i32 {
+        let values = unpack(&tensor.tensor_content, i32::from_le_bytes)?;
+        return Ok(values.into_iter().map(i64::from).collect());
+    }
+    unpack(&tensor.tensor_content, i64::from_le_bytes)
+}
+
+fn unpack<T, B, F>(content: &[u8], from_le_bytes: F) -> Result<Vec<T>>
+where
+    B: Default + AsMut<[u8]>,
+    F: Fn(B) -> T,
+{
+    let size = B::default().as_mut().len();
+    let chunks = content.chunks_exact(size);
+    if !chunks.remainder().is_empty() {
+        return Err(format!(
+            "tensor content of {} bytes is not a whole number of {} byte values",
+            content.len(),
+            size
+        )
+        .into());
+    }
+    Ok(chunks
+        .map(|chunk| {
+            let mut bytes = B::default();
+            bytes.as_mut().copy_from_slice(chunk);
+            from_le_bytes(bytes)
+        })
+        .collect())
+}
+
+/// Complex values of a `DT_COMPLEX64` tensor as (real, imaginary) pairs
+///
+/// Returns an error if `scomplex_val` holds an odd number of values.
+///
+/// ```rust
+/// # use tensorflow_serving::{as_complex64, Payload};
+/// let tensor = Payload::Complex64(vec![(1.0, -1.0), (0.5, 2.0)]).into_tensor(&[2]);
+/// assert_eq!(tensor.scomplex_val, vec![1.0, -1.0, 0.5, 2.0]);
+/// assert_eq!(as_complex64(&tensor).unwrap(), vec![(1.0, -1.0), (0.5, 2.0)]);
+/// ```
+pub fn as_complex64(tensor: &TensorProto) -> Result<Vec<(f32, f32)>> {
+    let values = &tensor.scomplex_val;
+    let pairs = values.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(format!(
+            "complex tensor has an odd number of values ({})",
+            values.len()
+        )
+        .into());
+    }
+    Ok(pairs.map(|c| (c[0], c[1])).collect())
+}
+
+/// Values of a `DT_UINT16` tensor
+///
+/// 16-bit values are stored widened in `int_val`. Returns an error if any value is out of
+/// range for a `u16`.
+pub fn as_uint16(tensor: &TensorProto) -> Result<Vec<u16>> {
+    narrow_int_val(tensor)
+}
+
+/// Values of a `DT_INT16` tensor
+///
+/// 16-bit values are stored widened in `int_val`. Returns an error if any value is out of
+/// range for an `i16`.
+pub fn as_int16(tensor: &TensorProto) -> Result<Vec<i16>> {
+    narrow_int_val(tensor)
+}
+
+fn narrow_int_val<T: TryFrom<i32>>(tensor: &TensorProto) -> Result<Vec<T>> {
+    tensor
+        .int_val
+        .iter()
+        .map(|&v| T::try_from(v).map_err(|_| format!("tensor value {} is out of range", v).into()))
+        .collect()
+}
+
+/// Remove a leading batch dimension of size 1 from a tensor shape
+///
+/// Single inputs are sent with a batch size of 1, so outputs usually come back with a leading
+/// dimension of size 1. This is a no-op if the tensor has no dimensions, or the first
+/// dimension is not 1. The values are unchanged, as squeezing does not reorder them.
+///
+/// ```rust
+/// # use tensorflow_serving::{squeeze_batch, Payload};
+/// let mut tensor = Payload::Floats(vec![0.1, 0.9]).into_tensor(&[1, 2]);
+/// squeeze_batch(&mut tensor);
+/// assert_eq!(tensor.tensor_shape.unwrap().dim.len(), 1);
+///
+/// let mut batch = Payload::Floats(vec![0.1, 0.9]).into_tensor(&[2, 1]);
+/// squeeze_batch(&mut batch);
+/// assert_eq!(batch.tensor_shape.unwrap().dim.len(), 2);
+/// ```
+pub fn squeeze_batch(tensor: &mut TensorProto) {
+    if let Some(shape) = &mut tensor.tensor_shape {
+        if shape.dim.first().map(|d| d.size) == Some(1) {
+            shape.dim.remove(0);
+        }
+    }
+}
+
+/// An all-zero tensor matching a signature input
+///
+/// Unknown dimensions, including the batch dimension, are given a size of 1, so the tensor
+/// is as small as the signature allows. Image inputs become a single black image.
+pub(crate) fn zero_tensor(info: &TensorInfo) -> Result<TensorProto> {
+    let dims: Vec<i64> = info
+        .tensor_shape
+        .as_ref()
+        .map(|shape| shape.dim.iter().map(|d| d.size.max(1)).collect())
+        .unwrap_or_default();
+    let n = dims.iter().product::<i64>() as usize;
+
+    let mut tensor = TensorProto {
+        dtype: info.dtype,
+        tensor_shape: Some(tensor_shape(&dims)),
+        ..Default::default()
+    };
+    match DataType::from_i32(info.dtype) {
+        Some(DataType::DtFloat) => tensor.float_val = vec![0.0; n],
+        Some(DataType::DtDouble) => tensor.double_val = vec![0.0; n],
+        Some(DataType::DtInt8)
+        | Some(DataType::DtUint8)
+        | Some(DataType::DtInt16)
+        | Some(DataType::DtUint16)
+        | Some(DataType::DtInt32) => tensor.int_val = vec![0; n],
+        Some(DataType::DtInt64) => tensor.int64_val = vec![0; n],
+        Some(DataType::DtBool) => tensor.bool_val = vec![false; n],
+        Some(DataType::DtString) => tensor.string_val = vec![Vec::new(); n],
+        other => {
+            return Err(format!(
+                "cannot build a zero tensor of type {:?}",
+                other.unwrap_or(DataType::DtInvalid)
+            )
+            .into())
+        }
+    }
+    Ok(tensor)
+}
+
+/// Flatten (real, imaginary) pairs into interleaved values
+pub(crate) fn interleave(values: &[(f32, f32)]) -> Vec<f32> {
+    values.iter().flat_map(|&(re, im)| [re, im]).collect()
+}
+
+pub(crate) fn tensor_shape(dims: &[i64]) -> TensorShapeProto {
+    TensorShapeProto {
+        dim: dims
+            .iter()
+            .map(|d| tensor_shape_proto::Dim {
+                size: *d,
+                name: "".to_string(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
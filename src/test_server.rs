@@ -0,0 +1,163 @@
+//! In-process prediction server for unit tests, recording the calls it receives
+
+use crate::tensorflow::tensorflow_serving::prediction_service_server::{
+    PredictionService, PredictionServiceServer,
+};
+use crate::tensorflow::tensorflow_serving::{
+    ClassificationRequest, ClassificationResponse, GetModelMetadataRequest,
+    GetModelMetadataResponse, MultiInferenceRequest, MultiInferenceResponse, RegressionRequest,
+    RegressionResponse,
+};
+use crate::{Payload, PredictRequest, PredictResponse, TensorflowServing};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::body::BoxBody;
+use tonic::codegen::http;
+use tonic::codegen::{HyperBody, Service};
+use tonic::metadata::MetadataMap;
+use tonic::transport::{NamedService, Server};
+use tonic::{Request, Response, Status};
+
+/// Name the server also exposes the prediction service under
+pub(crate) const RENAMED_SERVICE: &str = "my.pkg.PredictionService";
+
+/// Calls received by the server
+#[derive(Debug, Default)]
+pub(crate) struct Log {
+    /// Request paths of calls to the renamed service
+    pub(crate) renamed_paths: Vec<String>,
+    /// Metadata of every predict call
+    pub(crate) metadata: Vec<MetadataMap>,
+    /// Every predict request
+    pub(crate) predictions: Vec<PredictRequest>,
+}
+
+/// Start a server answering predictions with two class probabilities, and connect to it
+///
+/// `configure` is applied to the builder before the client is built.
+pub(crate) async fn connect<F>(configure: F) -> (TensorflowServing, Arc<Mutex<Log>>)
+where
+    F: Fn(&mut crate::TensorflowServingBuilder),
+{
+    let log = Arc::new(Mutex::new(Log::default()));
+    let server = PredictionServiceServer::new(Recorder { log: log.clone() });
+    let renamed = Renamed {
+        inner: server.clone(),
+        log: log.clone(),
+    };
+
+    let addr: SocketAddr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    tokio::spawn(
+        Server::builder()
+            .add_service(server)
+            .add_service(renamed)
+            .serve(addr),
+    );
+
+    // The server binds its port once the spawned task first runs
+    for _ in 0..100 {
+        let mut builder = TensorflowServing::new();
+        builder.hostname("127.0.0.1").port(addr.port());
+        configure(&mut builder);
+        if let Ok(client) = builder.build().await {
+            return (client, log);
+        }
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("test server did not start on {}", addr);
+}
+
+struct Recorder {
+    log: Arc<Mutex<Log>>,
+}
+
+#[tonic::async_trait]
+impl PredictionService for Recorder {
+    async fn classify(
+        &self,
+        _: Request<ClassificationRequest>,
+    ) -> Result<Response<ClassificationResponse>, Status> {
+        Err(Status::unimplemented("classify"))
+    }
+
+    async fn regress(
+        &self,
+        _: Request<RegressionRequest>,
+    ) -> Result<Response<RegressionResponse>, Status> {
+        Err(Status::unimplemented("regress"))
+    }
+
+    async fn predict(
+        &self,
+        request: Request<PredictRequest>,
+    ) -> Result<Response<PredictResponse>, Status> {
+        let mut log = self.log.lock().unwrap();
+        log.metadata.push(request.metadata().clone());
+        log.predictions.push(request.into_inner());
+
+        let mut response = PredictResponse::default();
+        response.outputs.insert(
+            "probabilities".to_string(),
+            Payload::Floats(vec![0.2, 0.8]).into_tensor(&[1, 2]),
+        );
+        response.outputs.insert(
+            "classes".to_string(),
+            Payload::Ints(vec![1]).into_tensor(&[1]),
+        );
+        Ok(Response::new(response))
+    }
+
+    async fn multi_inference(
+        &self,
+        _: Request<MultiInferenceRequest>,
+    ) -> Result<Response<MultiInferenceResponse>, Status> {
+        Err(Status::unimplemented("multi_inference"))
+    }
+
+    async fn get_model_metadata(
+        &self,
+        _: Request<GetModelMetadataRequest>,
+    ) -> Result<Response<GetModelMetadataResponse>, Status> {
+        Err(Status::unimplemented("get_model_metadata"))
+    }
+}
+
+type Inner = PredictionServiceServer<Recorder>;
+
+/// The prediction service under [`RENAMED_SERVICE`], recording the paths it is called on
+#[derive(Clone)]
+struct Renamed {
+    inner: Inner,
+    log: Arc<Mutex<Log>>,
+}
+
+impl NamedService for Renamed {
+    const NAME: &'static str = RENAMED_SERVICE;
+}
+
+impl Service<http::Request<HyperBody>> for Renamed {
+    type Response = http::Response<BoxBody>;
+    type Error = <Inner as Service<http::Request<HyperBody>>>::Error;
+    type Future = <Inner as Service<http::Request<HyperBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<HyperBody>) -> Self::Future {
+        let (mut parts, body) = request.into_parts();
+        let path = parts.uri.path().to_string();
+        self.log.lock().unwrap().renamed_paths.push(path.clone());
+
+        let method = path.rsplit('/').next().unwrap_or_default();
+        parts.uri = format!("/{}/{}", crate::service::PREDICTION_SERVICE, method)
+            .parse()
+            .unwrap();
+        self.inner.call(http::Request::from_parts(parts, body))
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{as_floats, PredictResponse, Result, TensorflowServingError};
+
+/// Prediction for one tile of a larger image
+///
+/// Returned by [predict_tiled](struct.TensorflowServing.html#method.predict_tiled). The
+/// position and size give the region of the source image covered by the tile.
+#[derive(Debug, Clone)]
+pub struct Tile {
+    /// Horizontal offset of the tile within the source image
+    pub x: u32,
+    /// Vertical offset of the tile within the source image
+    pub y: u32,
+    /// Width of the tile
+    pub width: u32,
+    /// Height of the tile
+    pub height: u32,
+    /// Response from the server for this tile
+    pub response: PredictResponse,
+}
+
+/// Reassemble per-tile outputs into a single output covering the whole image
+///
+/// The named output of each tile must hold one value per pixel per channel, in row-major
+/// (height, width, channel) order, as produced by segmentation models whose output has the
+/// same spatial size as their input. The number of channels is inferred from the size of the
+/// output.
+///
+/// Where tiles overlap, the value for each pixel is the mean of the values predicted by every
+/// tile covering that pixel.
+///
+/// Returns a row-major (height, width, channel) buffer for an image of the given `width` and
+/// `height`. Outputs may hold their values in `float_val` or packed in `tensor_content`:
+///
+/// ```rust
+/// # use tensorflow_serving::{pack_tensor_content, stitch_tiles, Payload, PredictResponse, Tile};
+/// let tile = |x, value| {
+///     let mut output = Payload::Floats(vec![value]).into_tensor(&[1, 1, 1, 1]);
+///     pack_tensor_content(&mut output);
+///     let mut response = PredictResponse::default();
+///     response.outputs.insert("mask".to_string(), output);
+///     Tile { x, y: 0, width: 1, height: 1, response }
+/// };
+///
+/// let stitched = stitch_tiles(&[tile(0, 0.25), tile(1, 0.75)], "mask", 2, 1).unwrap();
+/// assert_eq!(stitched, vec![0.25, 0.75]);
+/// ```
+pub fn stitch_tiles(
+    tiles: &[Tile],
+    output_name: &str,
+    width: u32,
+    height: u32,
+) -> Result<Vec<f32>> {
+    let mut channels = None;
+    let mut sums = Vec::new();
+    let mut counts = vec![0u32; (width * height) as usize];
+
+    for tile in tiles {
+        let output = tile
+            .response
+            .outputs
+            .get(output_name)
+            .ok_or_else(|| TensorflowServingError::MissingOutput(output_name.to_string()))?;
+        let values = as_floats(output)?;
+
+        let pixels = (tile.width * tile.height) as usize;
+        if pixels == 0 || values.len() % pixels != 0 {
+            return Err(format!(
+                "output {} has {} values, which does not match a {}x{} tile",
+                output_name,
+                values.len(),
+                tile.width,
+                tile.height
+            )
+            .into());
+        }
+
+        let tile_channels = values.len() / pixels;
+        let n_channels = *channels.get_or_insert(tile_channels);
+        if tile_channels != n_channels {
+            return Err("tiles have differing numbers of output channels".into());
+        }
+        if sums.is_empty() {
+            sums = vec![0.0f32; (width * height) as usize * n_channels];
+        }
+
+        if tile.x + tile.width > width || tile.y + tile.height > height {
+            return Err("tile lies outside of the output image".into());
+        }
+
+        for ty in 0..tile.height {
+            for tx in 0..tile.width {
+                let src = (ty * tile.width + tx) as usize * n_channels;
+                let pixel = ((tile.y + ty) * width + tile.x + tx) as usize;
+                let dst = pixel * n_channels;
+                for c in 0..n_channels {
+                    sums[dst + c] += values[src + c];
+                }
+                counts[pixel] += 1;
+            }
+        }
+    }
+
+    let n_channels = channels.ok_or("no tiles to stitch")?;
+    for (pixel, count) in counts.iter().enumerate() {
+        if *count > 1 {
+            for value in &mut sums[pixel * n_channels..(pixel + 1) * n_channels] {
+                *value /= *count as f32;
+            }
+        }
+    }
+
+    Ok(sums)
+}
+
+/// Offsets of tiles along one axis of length `len`
+///
+/// Tiles are placed every `tile_size - overlap` pixels, and the final tile is aligned with the
+/// end of the axis so the whole axis is covered with full size tiles.
+pub(crate) fn tile_offsets(len: u32, tile_size: u32, overlap: u32) -> Vec<u32> {
+    if len <= tile_size {
+        return vec![0];
+    }
+
+    let step = tile_size - overlap;
+    let last = len - tile_size;
+    let mut offsets: Vec<u32> = (0..last).step_by(step as usize).collect();
+    offsets.push(last);
+    offsets
+}
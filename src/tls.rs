@@ -0,0 +1,58 @@
+use crate::{Result, TensorflowServingError};
+use std::path::PathBuf;
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+/// TLS settings given to the builder, as paths to PEM files
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TlsOptions {
+    pub(crate) ca_cert: Option<PathBuf>,
+    pub(crate) client_cert: Option<PathBuf>,
+    pub(crate) client_key: Option<PathBuf>,
+    pub(crate) domain: Option<String>,
+}
+
+impl TlsOptions {
+    /// Whether any TLS setting was given, so the connection should use TLS
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.ca_cert.is_some()
+            || self.client_cert.is_some()
+            || self.client_key.is_some()
+            || self.domain.is_some()
+    }
+
+    /// Read the certificate files and assemble the tonic TLS config
+    ///
+    /// A client certificate and key must be given together, for mutual TLS.
+    pub(crate) fn client_config(&self) -> Result<ClientTlsConfig> {
+        let mut config = ClientTlsConfig::new();
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path)?;
+            // tonic panics on an invalid CA certificate, so catch the obvious mistakes first
+            if !String::from_utf8_lossy(&pem).contains("-----BEGIN CERTIFICATE-----") {
+                return Err(TensorflowServingError::Builder(format!(
+                    "{} is not a PEM certificate",
+                    path.display()
+                )));
+            }
+            config = config.ca_certificate(Certificate::from_pem(pem));
+        }
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                config = config.identity(Identity::from_pem(
+                    std::fs::read(cert)?,
+                    std::fs::read(key)?,
+                ));
+            }
+            (None, None) => {}
+            _ => {
+                return Err(TensorflowServingError::Builder(
+                    "tls_client_cert and tls_client_key must be given together".to_string(),
+                ))
+            }
+        }
+        if let Some(domain) = &self.domain {
+            config = config.domain_name(domain.clone());
+        }
+        Ok(config)
+    }
+}